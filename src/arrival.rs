@@ -0,0 +1,349 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fmt,
+    sync::Arc,
+};
+use ::rand::{
+    rngs::SmallRng,
+    Rng,
+};
+use rustc_serialize::json::Json;
+
+use crate::{
+    config::{
+        ConfigError,
+        get_f64,
+        get_f64_array,
+        get_opt_string,
+        get_string,
+        get_usize,
+        at_least_one,
+    },
+    distribution::exponential,
+    trace::Trace,
+};
+
+// Quick Explanation of this
+// The arrival times of the requests come from an 'ArrivalProcess', chosen by "packets.distribution".
+// Its parameters are in the "packets" section, and rates are always in pps (one tick is one nanosecond).
+// A process is shared by all runs: each run gets its own sequence of arrivals from 'arrivals', which owns
+// everything it needs so the simulation can pull the arrivals lazily, as the time advances.
+// A new process only needs its type and an entry in 'ARRIVALS'.
+
+const TICKS_PER_SECOND: f64 = 1_000_000_000.0;
+
+pub trait ArrivalProcess: fmt::Debug + Send + Sync {
+    // Arrival times (in ticks, not decreasing) of one run, drawing from 'rng'. They may end (e.g. a trace, or a
+    // piecewise rate that ends at 0), then the run has fewer requests than "packets.nr_packets"
+    fn arrivals(self: Arc<Self>, rng: SmallRng) -> Arrivals;
+
+    // Mean arrival rate (in pps) over the first 'duration' ticks, reported as the offered load
+    fn mean_rate(&self, duration: usize) -> f64;
+}
+
+pub type Arrivals = Box<dyn Iterator<Item = usize> + Send>;
+
+type ArrivalBuilder = fn(&Json) -> Result<Arc<dyn ArrivalProcess>, ConfigError>;
+
+// Parameters (besides "distribution"), in the "packets" section:
+const ARRIVALS: &[(&str, ArrivalBuilder)] = &[
+    // rate
+    ("constant", |json| Ok(Arc::new(Constant { interval: (TICKS_PER_SECOND / positive(json, "packets.rate")?) as usize }))),
+    // rate (Poisson arrivals)
+    ("exponential", |json| Ok(Arc::new(Poisson { rate: positive(json, "packets.rate")? }))),
+    // rates and mean_durations (in ticks), one per state: a Poisson process whose rate changes with the state.
+    // Each state lasts an exponential time, then the process moves to any other state with the same probability.
+    ("mmpp", |json| {
+        let rates: Vec<f64> = get_f64_array(json, "packets.rates")?;
+        let mean_durations: Vec<f64> = get_f64_array(json, "packets.mean_durations")?;
+        if rates.is_empty() || rates.len() != mean_durations.len() {
+            return Err(ConfigError::invalid("packets.mean_durations", format!("should have one duration per rate ({}), found {}", rates.len(), mean_durations.len())));
+        }
+        check_rates("packets.rates", &rates)?;
+        if let Some(duration) = mean_durations.iter().find(|duration| **duration <= 0.0) {
+            return Err(ConfigError::invalid("packets.mean_durations", format!("should be bigger than 0, found {}", duration)));
+        }
+        Ok(Arc::new(Mmpp { rates, mean_durations }))
+    }),
+    // rate (Poisson arrivals during a burst), burst_size (requests per burst) and gap (ticks between bursts)
+    ("onoff", |json| Ok(Arc::new(OnOff {
+        rate: positive(json, "packets.rate")?,
+        burst_size: {
+            let burst_size: usize = get_usize(json, "packets.burst_size")?;
+            at_least_one("packets.burst_size", burst_size)?;
+            burst_size
+        },
+        gap: get_usize(json, "packets.gap")?,
+    }))),
+    // times (in ticks) and rates, with interpolation "step" (default) or "linear" between them.
+    // Before the first time and after the last one, the rate is the first/last rate (the arrivals end at the last
+    // time when the last rate is 0).
+    ("piecewise", |json| {
+        let times: Vec<f64> = get_f64_array(json, "packets.times")?;
+        let rates: Vec<f64> = get_f64_array(json, "packets.rates")?;
+        if times.is_empty() || times.len() != rates.len() {
+            return Err(ConfigError::invalid("packets.rates", format!("should have one rate per time ({}), found {}", times.len(), rates.len())));
+        }
+        if times.windows(2).any(|w| w[1] < w[0]) || times[0] < 0.0 {
+            return Err(ConfigError::invalid("packets.times", "should not be negative nor decrease".to_string()));
+        }
+        check_rates("packets.rates", &rates)?;
+        let linear: bool = match get_opt_string(json, "packets.interpolation")?.as_deref() {
+            None | Some("step") => false,
+            Some("linear") => true,
+            Some(other) => return Err(ConfigError::invalid("packets.interpolation", format!("should be \"step\" or \"linear\", found \"{}\"", other))),
+        };
+        Ok(Arc::new(Piecewise { times, rates, linear }))
+    }),
+];
+
+pub fn from_json(json: &Json) -> Result<Arc<dyn ArrivalProcess>, ConfigError> {
+    let path: &str = "packets.distribution";
+    let name: String = get_string(json, path)?;
+
+    match ARRIVALS.iter().find(|(n, _)| *n == name) {
+        Some((_, builder)) => builder(json),
+        None => {
+            let names: Vec<&str> = ARRIVALS.iter().map(|(n, _)| *n).collect();
+            Err(ConfigError::invalid(path, format!("should be one of {}, found \"{}\"", names.join(", "), name)))
+        },
+    }
+}
+
+fn positive(json: &Json, path: &str) -> Result<f64, ConfigError> {
+    let value: f64 = get_f64(json, path)?;
+    if value <= 0.0 {
+        return Err(ConfigError::invalid(path, format!("should be bigger than 0, found {}", value)));
+    }
+    Ok(value)
+}
+
+// Rates may be 0 (silent periods), but not all of them
+fn check_rates(path: &str, rates: &[f64]) -> Result<(), ConfigError> {
+    if let Some(rate) = rates.iter().find(|rate| **rate < 0.0) {
+        return Err(ConfigError::invalid(path, format!("should not be negative, found {}", rate)));
+    }
+    if rates.iter().all(|rate| *rate == 0.0) {
+        return Err(ConfigError::invalid(path, "should have at least one rate bigger than 0".to_string()));
+    }
+    Ok(())
+}
+
+// Structure
+#[derive(Debug)]
+pub struct Constant {
+    pub interval: usize,
+}
+
+impl ArrivalProcess for Constant {
+    fn arrivals(self: Arc<Self>, _rng: SmallRng) -> Arrivals {
+        Box::new((1..).map(move |i| i * self.interval))
+    }
+
+    fn mean_rate(&self, _duration: usize) -> f64 {
+        TICKS_PER_SECOND / self.interval.max(1) as f64
+    }
+}
+
+#[derive(Debug)]
+pub struct Poisson {
+    pub rate: f64,
+}
+
+impl ArrivalProcess for Poisson {
+    fn arrivals(self: Arc<Self>, mut rng: SmallRng) -> Arrivals {
+        let lambda: f64 = self.rate / TICKS_PER_SECOND;
+        let mut t: usize = 0;
+        Box::new(std::iter::from_fn(move || {
+            t += exponential(&mut rng, lambda) as usize;
+            Some(t)
+        }))
+    }
+
+    fn mean_rate(&self, _duration: usize) -> f64 {
+        self.rate
+    }
+}
+
+#[derive(Debug)]
+pub struct Mmpp {
+    pub rates: Vec<f64>,
+    pub mean_durations: Vec<f64>,
+}
+
+impl ArrivalProcess for Mmpp {
+    fn arrivals(self: Arc<Self>, mut rng: SmallRng) -> Arrivals {
+        let n: usize = self.rates.len();
+        let mut state: usize = 0;
+        let mut t: f64 = 0.0;
+        let mut t_state_end: f64 = exponential(&mut rng, 1.0 / self.mean_durations[0]);
+
+        Box::new(std::iter::from_fn(move || loop {
+            // The exponential is memoryless, so an arrival past the end of the state is drawn again in the next one
+            let rate: f64 = self.rates[state];
+            let t_next: f64 = if rate > 0.0 { t + exponential(&mut rng, rate / TICKS_PER_SECOND) } else { f64::INFINITY };
+            if t_next <= t_state_end {
+                t = t_next;
+                return Some(t as usize);
+            }
+
+            t = t_state_end;
+            if n > 1 {
+                state = (state + 1 + rng.gen_range(0..n - 1)) % n;
+            }
+            t_state_end = t + exponential(&mut rng, 1.0 / self.mean_durations[state]);
+        }))
+    }
+
+    fn mean_rate(&self, _duration: usize) -> f64 {
+        // Every state is visited as often, so the time in each one is proportional to its mean duration
+        let total: f64 = self.mean_durations.iter().sum();
+        self.rates.iter().zip(&self.mean_durations).map(|(rate, duration)| rate * duration).sum::<f64>() / total
+    }
+}
+
+#[derive(Debug)]
+pub struct OnOff {
+    pub rate: f64,
+    pub burst_size: usize,
+    pub gap: usize,
+}
+
+impl ArrivalProcess for OnOff {
+    fn arrivals(self: Arc<Self>, mut rng: SmallRng) -> Arrivals {
+        let lambda: f64 = self.rate / TICKS_PER_SECOND;
+        let mut t: usize = 0;
+        let mut i: usize = 0;
+        Box::new(std::iter::from_fn(move || {
+            if i > 0 && i.is_multiple_of(self.burst_size) {
+                t += self.gap;
+            }
+            i += 1;
+            t += exponential(&mut rng, lambda) as usize;
+            Some(t)
+        }))
+    }
+
+    fn mean_rate(&self, _duration: usize) -> f64 {
+        let burst: f64 = self.burst_size as f64 / self.rate;
+        self.burst_size as f64 / (burst + self.gap as f64 / TICKS_PER_SECOND)
+    }
+}
+
+#[derive(Debug)]
+pub struct Piecewise {
+    pub times: Vec<f64>,
+    pub rates: Vec<f64>,
+    pub linear: bool,
+}
+
+impl Piecewise {
+    fn rate_at(&self, t: f64) -> f64 {
+        let i: usize = self.times.partition_point(|time| *time <= t);
+        if i == 0 {
+            return self.rates[0];
+        }
+        if i == self.times.len() || !self.linear {
+            return self.rates[i - 1];
+        }
+        let (t0, t1): (f64, f64) = (self.times[i - 1], self.times[i]);
+        let (r0, r1): (f64, f64) = (self.rates[i - 1], self.rates[i]);
+        r0 + (r1 - r0) * (t - t0) / (t1 - t0)
+    }
+}
+
+impl ArrivalProcess for Piecewise {
+    fn arrivals(self: Arc<Self>, mut rng: SmallRng) -> Arrivals {
+        // Thinning: candidates at the biggest rate, each one kept with probability rate(t)/biggest rate
+        let rate_max: f64 = self.rates.iter().cloned().fold(0.0, f64::max);
+        // Past the last time, a last rate of 0 never gives another arrival
+        let t_end: f64 = match self.rates.last() {
+            Some(rate) if *rate == 0.0 => *self.times.last().unwrap(),
+            _ => f64::INFINITY,
+        };
+        let mut t: f64 = 0.0;
+        Box::new(std::iter::from_fn(move || loop {
+            t += exponential(&mut rng, rate_max / TICKS_PER_SECOND);
+            if t >= t_end {
+                return None;
+            }
+            if rng.gen::<f64>() * rate_max < self.rate_at(t) {
+                return Some(t as usize);
+            }
+        }))
+    }
+
+    fn mean_rate(&self, duration: usize) -> f64 {
+        // Average of the rate over [0, duration), in steps of 1/1000 of the duration
+        let nr_steps: usize = 1000;
+        let step: f64 = duration.max(1) as f64 / nr_steps as f64;
+        (0..nr_steps).map(|i| self.rate_at((i as f64 + 0.5) * step)).sum::<f64>() / nr_steps as f64
+    }
+}
+
+impl ArrivalProcess for Trace {
+    fn arrivals(self: Arc<Self>, _rng: SmallRng) -> Arrivals {
+        Box::new((0..self.records.len()).map(move |i| self.records[i].t_arrival))
+    }
+
+    fn mean_rate(&self, _duration: usize) -> f64 {
+        self.rate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::rand::SeedableRng;
+
+    use super::*;
+    use crate::{
+        SimConfig,
+        Simulation,
+        SimulationResults,
+    };
+
+    fn packets(section: &str) -> Json {
+        let text: String = format!(r#"{{
+            "layout": 2, "duration": 1000000000, "queue_size": 64, "rtt_base": 8000, "nr_total_cores": 4,
+            "nr_indirection_table_entries": 128, "layout2": {{"nr_worker_cores": 4}},
+            "forwarder": {{"distribution": "constant", "mean1": 300}},
+            "network_stack": {{"distribution": "constant", "mean1": 800}},
+            "application": {{"distribution": "exponential", "mean1": 1000}},
+            "packets": {}
+        }}"#, section);
+        Json::from_str(&text).unwrap()
+    }
+
+    fn arrivals(section: &str) -> Vec<usize> {
+        from_json(&packets(section)).unwrap().arrivals(SmallRng::seed_from_u64(1)).take(1000000).collect()
+    }
+
+    #[test]
+    fn piecewise_ending_at_rate_0_ends() {
+        // 900000 pps during 2 ms, then nothing
+        let step: Vec<usize> = arrivals(r#"{"distribution": "piecewise", "times": [0, 2000000], "rates": [900000, 0]}"#);
+        assert!((1700..1900).contains(&step.len()), "{} arrivals", step.len());
+        assert!(step.iter().all(|t| *t < 2000000));
+
+        // Linear from 900000 pps down to 0 during 2 ms, so half as many
+        let linear: Vec<usize> = arrivals(r#"{"distribution": "piecewise", "times": [0, 2000000], "rates": [900000, 0], "interpolation": "linear"}"#);
+        assert!((800..1000).contains(&linear.len()), "{} arrivals", linear.len());
+
+        // A silent period in the middle does not end the arrivals
+        let silent: Vec<usize> = arrivals(r#"{"distribution": "piecewise", "times": [0, 1000000, 2000000], "rates": [900000, 0, 900000]}"#);
+        assert_eq!(silent.len(), 1000000);
+        assert!(!silent.iter().any(|t| (1000000..2000000).contains(t)));
+    }
+
+    #[test]
+    fn run_ends_when_the_arrivals_end() {
+        let json: Json = packets(r#"{"distribution": "piecewise", "times": [0, 2000000], "rates": [900000, 0], "nr_packets": 100000, "nr_flows": 16}"#);
+        let config: SimConfig = SimConfig::from_json(&json).unwrap();
+        let results: SimulationResults = Simulation::new(0, &config, false).unwrap().run().unwrap();
+        assert!(results.nr_packets < 2000, "{} requests", results.nr_packets);
+        assert_eq!(results.received, results.nr_packets);
+        assert_eq!(results.finished.len() + results.dropped.len(), results.nr_packets);
+    }
+}
@@ -0,0 +1,162 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    collections::{
+        HashMap,
+        HashSet,
+        VecDeque,
+    },
+    fmt,
+};
+
+use crate::{
+    request::Request,
+    layout::Layout,
+    results::{
+        DropRecord,
+        LatencyRecord,
+    },
+    worker_core::Core,
+};
+
+// Quick Explanation of this
+// Audit mode ("audit": true, or the number of steps between two checks): the simulation checks the invariants of the
+// server after the steps and fails with the first one that breaks. Every generated request is in exactly one place: waiting to arrive, in one core, finished
+// or dropped (and it is finished or dropped only once). A core running a request is not idle. A flow lock is only held
+// by a core running a request of that flow. The completed stages of every request (the forwarder, the network stack,
+// the application and the TX) start and end in this order, after its arrival and before its departure.
+// The finished and dropped requests are checked when they leave the server, through the 'Context' of the layout.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditError {
+    pub run_id: usize,
+    pub t_cur: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "run {}: audit failed at tick {}: {}", self.run_id, self.t_cur, self.reason)
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+// Structure
+#[derive(Default)]
+pub struct Auditor {
+    // Requests finished or dropped so far (every one of the run, so it grows with the number of requests)
+    outcomes: HashSet<usize>,
+    nr_finished_seen: usize,
+    nr_dropped_seen: usize,
+    // First broken invariant found while the requests left the server
+    violation: Option<String>,
+}
+
+// Associate Functions
+impl Auditor {
+    pub fn new() -> Auditor {
+        Auditor::default()
+    }
+
+    // A request leaving the server (finished or dropped)
+    pub fn check_request(&mut self, req: &Request) {
+        if self.violation.is_none() {
+            self.violation = check_timestamps(req).err();
+        }
+    }
+
+    // The broken invariant of a request that left the server, if any (checked after every step)
+    pub fn take_violation(&mut self) -> Option<String> {
+        self.violation.take()
+    }
+
+    // Checks the invariants of the server after a step
+    pub fn check(
+        &mut self,
+        nr_generated: usize,
+        pending: &VecDeque<Request>,
+        layout: &dyn Layout,
+        finished: &[LatencyRecord],
+        dropped: &[DropRecord],
+    ) -> Result<(), String> {
+        // Each request has at most one outcome
+        let new_outcomes = finished[self.nr_finished_seen..].iter().map(|record| record.id)
+            .chain(dropped[self.nr_dropped_seen..].iter().map(|record| record.id));
+        for id in new_outcomes {
+            if !self.outcomes.insert(id) {
+                return Err(format!("Request #{} was finished or dropped twice", id));
+            }
+        }
+        self.nr_finished_seen = finished.len();
+        self.nr_dropped_seen = dropped.len();
+
+        // Each request in the server is in a single place, and has no outcome yet
+        let cores: Vec<&Core> = layout.cores();
+        // The core of each request (None while it did not arrive)
+        let mut places: HashMap<usize, Option<usize>> = HashMap::<usize, Option<usize>>::new();
+        for req in pending.iter() {
+            places.insert(req.get_id(), None);
+        }
+        let place = |core_id: Option<usize>| match core_id {
+            None => "the arrivals".to_string(),
+            Some(core_id) => format!("Core #{}", core_id),
+        };
+        for core in cores.iter() {
+            if core.get_current_request().is_some() && core.is_idle() {
+                return Err(format!("Core #{} is idle while running the Request #{}", core.get_id(), core.get_current_request().unwrap().get_id()));
+            }
+            for req in core.requests() {
+                if let Some(other) = places.insert(req.get_id(), Some(core.get_id())) {
+                    return Err(format!("Request #{} is both in {} and in Core #{}", req.get_id(), place(other), core.get_id()));
+                }
+                if self.outcomes.contains(&req.get_id()) {
+                    return Err(format!("Request #{} is in Core #{} after it was finished or dropped", req.get_id(), core.get_id()));
+                }
+                check_timestamps(req)?;
+            }
+        }
+
+        // Every generated request is somewhere
+        let nr_in_flight: usize = places.len() - pending.len();
+        if nr_generated != pending.len() + nr_in_flight + finished.len() + dropped.len() {
+            return Err(format!(
+                "{} requests were generated, but {} are still to arrive, {} are in the server, {} finished and {} were dropped",
+                nr_generated, pending.len(), nr_in_flight, finished.len(), dropped.len(),
+            ));
+        }
+
+        // A flow lock is held by a core running a request of that flow, or with one preempted in its queue
+        if let Some(locks) = layout.locks() {
+            for (flow_id, owner) in locks.iter().enumerate().filter(|(_, owner)| **owner != usize::MAX) {
+                let holds: bool = cores.iter().filter(|core| core.get_id() == *owner).any(|core| {
+                    core.get_current_request().is_some_and(|req| req.get_flow_id() == flow_id)
+                        || core.requests().any(|req| req.is_preempted() && req.get_flow_id() == flow_id)
+                });
+                if !holds {
+                    return Err(format!("the lock of the flow {} is held by Core #{}, which has no running or preempted request of it", flow_id, owner));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// The completed stages of 'req' ran in order, after its arrival (and before its departure, if it departed)
+fn check_timestamps(req: &Request) -> Result<(), String> {
+    let mut t_last: usize = req.get_arrival_time();
+    let mut last: &str = "arrival";
+    for (stage, _, t_start, t_end) in req.get_stages().into_iter().filter(|(_, is_completed, _, _)| *is_completed) {
+        if t_start < t_last || t_end < t_start {
+            return Err(format!("Request #{}: the {} ran from {} to {}, but the {} was at {}", req.get_id(), stage, t_start, t_end, last, t_last));
+        }
+        t_last = t_end;
+        last = stage;
+    }
+    if req.get_departure_time() != 0 && req.get_departure_time() <= t_last {
+        return Err(format!("Request #{}: departed at {}, but the {} was at {}", req.get_id(), req.get_departure_time(), last, t_last));
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fs::File,
+    io::Read,
+    path::{
+        Path,
+        PathBuf,
+    },
+    collections::BTreeMap,
+};
+use rustc_serialize::json::Json;
+
+use sim::sweep::Sweep;
+
+// Quick Explanation of this
+// Command-line options of the simulator. Every option only changes the configuration in memory,
+// so the same 'config.json' can be used for many runs from scripts.
+// The files named by the configuration (the "file" of an empirical distribution and "packets.trace") are relative
+// to the directory of the configuration file, while the ones given with --set or --sweep are relative to the
+// current directory.
+
+pub const USAGE: &str = "\
+Usage: sim [OPTIONS]
+
+Options:
+    --config <path>          configuration file (default: config.json)
+    --out-dir <dir>          directory of the output files (default: current directory)
+    --set <key.path=value>   overrides a configuration field (e.g. --set packets.rate=500000), can be repeated
+    --layout <n>             layout to simulate (same as --set layout=<n>)
+    --runs <n>               number of runs (same as --set runs=<n>)
+    --sweep <key.path=vals>  runs one simulation per value, vals is v1,v2,... or start:end:step
+                             (e.g. --sweep packets.rate=100000:1000000:50000), results in <out-dir>/sweep.csv
+    --jobs <n>               number of simulations running at the same time (default: number of CPUs),
+                             the results do not depend on it
+    --json                   also writes every finished/dropped request of each run to layout<n>_run<i>.json
+    --audit                  checks the invariants of the server after every step, failing when one breaks
+                             (slow, same as --set audit=true, --set audit=<n> checks every n steps)
+    -h, --help               prints this message";
+
+// Structure
+pub struct Cli {
+    pub config: PathBuf,
+    pub out_dir: PathBuf,
+    pub overrides: Vec<(String, String)>,
+    pub sweep: Option<Sweep>,
+    pub jobs: Option<usize>,
+    pub json: bool,
+    pub help: bool,
+}
+
+// Associate Functions
+impl Cli {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Cli, String> {
+        let mut cli: Cli = Cli {
+            config: PathBuf::from("config.json"),
+            out_dir: PathBuf::from("."),
+            overrides: Vec::<(String, String)>::new(),
+            sweep: None,
+            jobs: None,
+            json: false,
+            help: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => cli.config = PathBuf::from(next_value(&mut args, &arg)?),
+                "--out-dir" => cli.out_dir = PathBuf::from(next_value(&mut args, &arg)?),
+                "--set" => {
+                    let value: String = next_value(&mut args, &arg)?;
+                    match value.split_once('=') {
+                        Some((path, value)) if !path.is_empty() => cli.overrides.push((path.to_string(), value.to_string())),
+                        _ => return Err(format!("'{}' expects <key.path=value>, got '{}'", arg, value)),
+                    }
+                },
+                "--layout" | "--runs" => {
+                    let value: String = next_value(&mut args, &arg)?;
+                    parse_number(&arg, &value)?;
+                    cli.overrides.push((arg[2..].to_string(), value));
+                },
+                "--sweep" => cli.sweep = Some(Sweep::parse(&next_value(&mut args, &arg)?)?),
+                "--jobs" => {
+                    let value: String = next_value(&mut args, &arg)?;
+                    match parse_number(&arg, &value)? {
+                        0 => return Err(format!("'{}' should be at least 1", arg)),
+                        jobs => cli.jobs = Some(jobs),
+                    }
+                },
+                "--json" => cli.json = true,
+                "--audit" => cli.overrides.push(("audit".to_string(), "true".to_string())),
+                "-h" | "--help" => cli.help = true,
+                _ => return Err(format!("unknown option '{}'", arg)),
+            }
+        }
+
+        Ok(cli)
+    }
+
+    // Reads the configuration file and applies the overrides on top of it
+    pub fn load_config(&self) -> Result<Json, String> {
+        let mut file: File = File::open(&self.config).map_err(|e| format!("cannot open {}: {}", self.config.display(), e))?;
+        let mut data: String = String::new();
+        file.read_to_string(&mut data).map_err(|e| format!("cannot read {}: {}", self.config.display(), e))?;
+        let mut json: Json = Json::from_str(&data).map_err(|e| format!("{} is not a valid JSON: {}", self.config.display(), e))?;
+        resolve_paths(&mut json, self.config.parent().unwrap_or(Path::new("")));
+
+        for (path, value) in &self.overrides {
+            apply_override(&mut json, path, value)?;
+        }
+
+        Ok(json)
+    }
+}
+
+// Fields holding the path of a file, wherever they are in the configuration
+const PATH_FIELDS: &[&str] = &["file", "trace"];
+
+// Makes the relative paths of 'PATH_FIELDS' relative to 'base' (the directory of the configuration file)
+fn resolve_paths(json: &mut Json, base: &Path) {
+    match json {
+        Json::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Json::String(path) if PATH_FIELDS.contains(&key.as_str()) && Path::new(path.as_str()).is_relative() => {
+                        *path = base.join(path.as_str()).to_string_lossy().into_owned();
+                    },
+                    _ => resolve_paths(value, base),
+                }
+            }
+        },
+        Json::Array(values) => {
+            for value in values.iter_mut() {
+                resolve_paths(value, base);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, option: &str) -> Result<String, String> {
+    args.next().ok_or(format!("'{}' expects a value", option))
+}
+
+fn parse_number(option: &str, value: &str) -> Result<usize, String> {
+    value.parse::<usize>().map_err(|_| format!("'{}' expects a number, got '{}'", option, value))
+}
+
+// Sets the field at 'path' (e.g. "packets.rate") to 'value', creating the missing objects in the way.
+// A number indexes an existing array element (e.g. "classes.0.ratio").
+// The value is read as JSON when possible (numbers, booleans, arrays...), otherwise it is taken as a string.
+pub fn apply_override(json: &mut Json, path: &str, value: &str) -> Result<(), String> {
+    let value: Json = Json::from_str(value).unwrap_or_else(|_| Json::String(value.to_string()));
+
+    let keys: Vec<&str> = path.split('.').collect();
+    let (last, parents) = keys.split_last().unwrap();
+    let mut node: &mut Json = json;
+    for (i, key) in parents.iter().enumerate() {
+        node = match node {
+            Json::Object(object) => object.entry(key.to_string()).or_insert_with(|| Json::Object(BTreeMap::new())),
+            Json::Array(values) => match key.parse::<usize>().ok().and_then(|idx| values.get_mut(idx)) {
+                Some(element) => element,
+                None => return Err(format!("cannot set '{}': '{}' has no element {}", path, keys[..i].join("."), key)),
+            },
+            _ => return Err(format!("cannot set '{}': '{}' is not an object", path, keys[..i].join("."))),
+        };
+    }
+
+    match node {
+        Json::Object(object) => {
+            object.insert(last.to_string(), value);
+            Ok(())
+        },
+        Json::Array(values) => match last.parse::<usize>().ok().and_then(|idx| values.get_mut(idx)) {
+            Some(element) => {
+                *element = value;
+                Ok(())
+            },
+            None => Err(format!("cannot set '{}': '{}' has no element {}", path, parents.join("."), last)),
+        },
+        _ => Err(format!("cannot set '{}': '{}' is not an object", path, parents.join("."))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_relative_to_the_configuration() {
+        let dir: PathBuf = std::env::temp_dir().join(format!("sim_cli_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config: PathBuf = dir.join("config.json");
+        std::fs::write(&config, r#"{
+            "application": {"distribution": "empirical", "file": "service.cdf"},
+            "classes": [{"application": {"distribution": "empirical", "file": "/data/service.dat"}}],
+            "packets": {"trace": "requests.csv"}
+        }"#).unwrap();
+
+        let args = ["--config", config.to_str().unwrap(), "--set", "network_stack.file=stack.dat"];
+        let cli: Cli = Cli::parse(args.iter().map(|arg| arg.to_string())).unwrap();
+        let json: Json = cli.load_config().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let path = |keys: &[&str]| json.find_path(keys).and_then(|value| value.as_string()).map(PathBuf::from);
+        assert_eq!(path(&["application", "file"]), Some(dir.join("service.cdf")));
+        assert_eq!(path(&["packets", "trace"]), Some(dir.join("requests.csv")));
+        // Absolute paths and the overrides are kept as they are
+        assert_eq!(json["classes"][0].find_path(&["application", "file"]).and_then(|value| value.as_string()), Some("/data/service.dat"));
+        assert_eq!(path(&["network_stack", "file"]), Some(PathBuf::from("stack.dat")));
+    }
+}
@@ -0,0 +1,468 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fmt,
+    sync::Arc,
+};
+use rustc_serialize::json::Json;
+
+use crate::{
+    distribution::{
+        self,
+        Distribution,
+    },
+    arrival::{
+        self,
+        ArrivalProcess,
+    },
+    flow::FlowPopularity,
+    trace::Trace,
+    discipline::{
+        self,
+        QueueDiscipline,
+    },
+    stealing::StealingConfig,
+    dispatcher::DispatcherConfig,
+};
+
+// Quick Explanation of this
+// The whole configuration is read and checked once, before the simulation starts.
+// Every error names the JSON path of the offending field and what was expected there.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Missing {
+        path: String,
+        expected: &'static str,
+    },
+    Type {
+        path: String,
+        expected: &'static str,
+        found: String,
+    },
+    Invalid {
+        path: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Missing { path, expected } => write!(f, "missing '{}' (expected {})", path, expected),
+            ConfigError::Type { path, expected, found } => write!(f, "'{}' should be {}, found {}", path, expected, found),
+            ConfigError::Invalid { path, reason } => write!(f, "invalid '{}': {}", path, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigError {
+    pub fn invalid(path: &str, reason: String) -> ConfigError {
+        ConfigError::Invalid {
+            path: path.to_string(),
+            reason,
+        }
+    }
+}
+
+// How the simulator advances the time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    // Jumps straight to the next tick where something happens (default)
+    Event,
+    // Visits every tick, as the original loop (useful to cross-check the event engine)
+    Tick,
+}
+
+#[derive(Debug, Clone)]
+pub struct NicConfig {
+    pub nr_indirection_table_entries: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout1Config {
+    pub nr_worker_cores: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout2Config {
+    pub nr_worker_cores: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout3Config {
+    pub nr_application_cores: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Layout4Config {
+    pub nr_network_cores: usize,
+    pub nr_application_cores: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PacketsConfig {
+    // Mean rate of the arrivals in pps (the offered load, 0 in closed loop)
+    pub rate: f64,
+    pub nr_packets: usize,
+    pub nr_flows: usize,
+    pub flows: FlowPopularity,
+    // None in closed loop, where the clients send the requests
+    pub arrivals: Option<Arc<dyn ArrivalProcess>>,
+    // Requests replayed from "packets.trace" instead of generated. Then the arrivals, the number of packets and the
+    // number of flows come from the trace ("packets.nr_packets" may still limit it), see 'Trace'
+    pub trace: Option<Arc<Trace>>,
+}
+
+// Closed loop: each connection sends its next request 'think_time' ticks after receiving the response of the last one
+// (the response takes 'rtt_base' ticks to reach the client), or after its last request was dropped
+#[derive(Debug, Clone)]
+pub struct ClientsConfig {
+    pub connections: usize,
+    pub think_time: Arc<dyn Distribution>,
+}
+
+// A class of requests, from the list in "classes": its share of the requests ("ratio"), the distributions of
+// its stages (its own sections "forwarder", "network_stack", "application" and "tx_stack", or else the global ones) and its
+// latency target ("slo", in ticks), if any. Without "classes", every request is of a single class "default".
+// The size of the requests and of their responses, in packets, is drawn from the sections "request_packets" and
+// "response_packets" of the class (or else of "packets"), see 'Generator'.
+#[derive(Debug, Clone)]
+pub struct ClassConfig {
+    pub name: String,
+    pub ratio: f64,
+    // Number of ticks used by each stage
+    pub forwarder: Arc<dyn Distribution>,
+    pub network_stack: Arc<dyn Distribution>,
+    pub application: Arc<dyn Distribution>,
+    // Number of ticks used to send each packet of the response (the network stack one without it)
+    pub tx_stack: Option<Arc<dyn Distribution>>,
+    // Packets per request (one without it) and per response (without it, one with "tx_stack" and none otherwise,
+    // so the response is not modeled)
+    pub request_packets: Option<Arc<dyn Distribution>>,
+    pub response_packets: Option<Arc<dyn Distribution>>,
+    pub slo: Option<usize>,
+}
+
+// Seed used when the configuration does not have one
+pub const DEFAULT_SEED: u64 = 7;
+
+// Structure
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    pub seed: u64,
+    // Number of replications, each one with its own random streams
+    pub runs: usize,
+    pub layout: usize,
+    pub engine: Engine,
+    pub duration: usize,
+    pub queue_size: usize,
+    pub rtt_base: usize,
+    pub nr_total_cores: usize,
+    pub nic: NicConfig,
+    // Only the section of the simulated layout is required
+    pub layout1: Option<Layout1Config>,
+    pub layout2: Option<Layout2Config>,
+    pub layout3: Option<Layout3Config>,
+    pub layout4: Option<Layout4Config>,
+    // At least one, see 'ClassConfig'
+    pub classes: Vec<ClassConfig>,
+    pub packets: PacketsConfig,
+    // Closed-loop clients, instead of the arrival process of "packets"
+    pub clients: Option<ClientsConfig>,
+    // Queueing discipline of the cores running the application, and of the forwarder and the network cores
+    pub discipline: Arc<dyn QueueDiscipline>,
+    pub network_discipline: Arc<dyn QueueDiscipline>,
+    // How the forwarder (Layout 1) and the network core (Layout 3) choose the worker of each request, and what they
+    // do when no worker can take it
+    pub dispatcher: DispatcherConfig,
+    // Work stealing between the cores running the application, if any
+    pub stealing: Option<StealingConfig>,
+    // Width (in ticks) of the windows of the time series of throughput and latency, if any
+    pub window: Option<usize>,
+    // Steps between two checks of the invariants of the server (slow, 1 checks after every step), see 'Auditor'
+    pub audit: Option<usize>,
+}
+
+// Associate Functions
+impl SimConfig {
+    pub fn from_json(json: &Json) -> Result<SimConfig, ConfigError> {
+        let config: SimConfig = SimConfig {
+            seed: get_opt_u64(json, "seed")?.unwrap_or(DEFAULT_SEED),
+            runs: get_opt_usize(json, "runs")?.unwrap_or(1),
+            layout: get_usize(json, "layout")?,
+            engine: match get_opt_string(json, "engine")?.as_deref() {
+                None | Some("event") => Engine::Event,
+                Some("tick") => Engine::Tick,
+                Some(other) => return Err(ConfigError::invalid("engine", format!("should be \"event\" or \"tick\", found \"{}\"", other))),
+            },
+            duration: get_usize(json, "duration")?,
+            queue_size: get_usize(json, "queue_size")?,
+            rtt_base: get_usize(json, "rtt_base")?,
+            nr_total_cores: get_usize(json, "nr_total_cores")?,
+            nic: NicConfig {
+                nr_indirection_table_entries: get_usize(json, "nr_indirection_table_entries")?,
+            },
+            layout1: match json.find("layout1") {
+                None => None,
+                Some(_) => Some(Layout1Config {
+                    nr_worker_cores: get_usize(json, "layout1.nr_worker_cores")?,
+                }),
+            },
+            layout2: match json.find("layout2") {
+                None => None,
+                Some(_) => Some(Layout2Config {
+                    nr_worker_cores: get_usize(json, "layout2.nr_worker_cores")?,
+                }),
+            },
+            layout3: match json.find("layout3") {
+                None => None,
+                Some(_) => Some(Layout3Config {
+                    nr_application_cores: get_usize(json, "layout3.nr_application_cores")?,
+                }),
+            },
+            layout4: match json.find("layout4") {
+                None => None,
+                Some(_) => Some(Layout4Config {
+                    nr_network_cores: get_usize(json, "layout4.nr_network_cores")?,
+                    nr_application_cores: get_usize(json, "layout4.nr_application_cores")?,
+                }),
+            },
+            classes: ClassConfig::from_json(json)?,
+            packets: PacketsConfig::from_json(json, get_usize(json, "duration")?, json.find("clients").is_some())?,
+            clients: match json.find("clients") {
+                None => None,
+                Some(_) => Some(ClientsConfig {
+                    connections: get_usize(json, "clients.connections")?,
+                    think_time: distribution::from_json(json, "clients.think_time")?,
+                }),
+            },
+            discipline: discipline::from_json(json, "discipline")?,
+            network_discipline: discipline::from_json(json, "network_discipline")?,
+            dispatcher: DispatcherConfig::from_json(json)?,
+            stealing: StealingConfig::from_json(json)?,
+            window: get_opt_usize(json, "window")?,
+            audit: match find(json, "audit") {
+                None | Some(Json::Boolean(false)) => None,
+                Some(Json::Boolean(true)) => Some(1),
+                Some(_) => Some(get_usize(json, "audit")?),
+            },
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        at_least_one("runs", self.runs)?;
+        at_least_one("nr_indirection_table_entries", self.nic.nr_indirection_table_entries)?;
+        at_least_one("packets.nr_packets", self.packets.nr_packets)?;
+        if let Some(clients) = &self.clients {
+            at_least_one("clients.connections", clients.connections)?;
+        }
+        if let Some(window) = self.window {
+            at_least_one("window", window)?;
+        }
+        if let Some(audit) = self.audit {
+            at_least_one("audit", audit)?;
+        }
+
+        // The layout checks its own section (number of cores, etc.)
+        crate::layout::build(self.layout, self)?;
+
+        Ok(())
+    }
+}
+
+impl ClassConfig {
+    fn from_json(json: &Json) -> Result<Vec<ClassConfig>, ConfigError> {
+        let nr_classes: usize = match find(json, "classes") {
+            None => return Ok(vec![ClassConfig {
+                name: "default".to_string(),
+                ratio: 1.0,
+                forwarder: distribution::from_json(json, "forwarder")?,
+                network_stack: distribution::from_json(json, "network_stack")?,
+                application: distribution::from_json(json, "application")?,
+                tx_stack: get_opt_distribution(json, "tx_stack")?,
+                request_packets: get_opt_distribution(json, "packets.request_packets")?,
+                response_packets: get_opt_distribution(json, "packets.response_packets")?,
+                slo: None,
+            }]),
+            Some(Json::Array(classes)) if !classes.is_empty() => classes.len(),
+            Some(value) => return Err(ConfigError::Type { path: "classes".to_string(), expected: "a non-empty array of objects", found: describe(value) }),
+        };
+
+        let classes: Vec<ClassConfig> = (0..nr_classes).map(|i| {
+            let section: String = format!("classes.{}", i);
+            // The section of the class, or else the global one
+            let stage = |name: &str| match (find(json, &format!("{}.{}", section, name)), find(json, name)) {
+                (None, Some(_)) => distribution::from_json(json, name),
+                _ => distribution::from_json(json, &format!("{}.{}", section, name)),
+            };
+            // The optional sections, which may be missing in both
+            let optional = |global: &str, name: &str| match find(json, &format!("{}.{}", section, name)) {
+                None => get_opt_distribution(json, global),
+                Some(_) => distribution::from_json(json, &format!("{}.{}", section, name)).map(Some),
+            };
+            let ratio: f64 = get_f64(json, &format!("{}.ratio", section))?;
+            if ratio < 0.0 {
+                return Err(ConfigError::invalid(&format!("{}.ratio", section), format!("should not be negative, found {}", ratio)));
+            }
+            Ok(ClassConfig {
+                name: get_opt_string(json, &format!("{}.name", section))?.unwrap_or(format!("class{}", i)),
+                ratio,
+                forwarder: stage("forwarder")?,
+                network_stack: stage("network_stack")?,
+                application: stage("application")?,
+                tx_stack: optional("tx_stack", "tx_stack")?,
+                request_packets: optional("packets.request_packets", "request_packets")?,
+                response_packets: optional("packets.response_packets", "response_packets")?,
+                slo: get_opt_usize(json, &format!("{}.slo", section))?,
+            })
+        }).collect::<Result<Vec<ClassConfig>, ConfigError>>()?;
+
+        if classes.iter().all(|class| class.ratio == 0.0) {
+            return Err(ConfigError::invalid("classes", "should have at least one ratio bigger than 0".to_string()));
+        }
+        Ok(classes)
+    }
+}
+
+impl PacketsConfig {
+    fn from_json(json: &Json, duration: usize, closed_loop: bool) -> Result<PacketsConfig, ConfigError> {
+        let file: String = match get_opt_string(json, "packets.trace")? {
+            Some(_) if closed_loop => return Err(ConfigError::invalid("packets.trace", "cannot be replayed by closed-loop clients".to_string())),
+            None => {
+                let arrivals: Option<Arc<dyn ArrivalProcess>> = if closed_loop { None } else { Some(arrival::from_json(json)?) };
+                let nr_flows: usize = get_usize(json, "packets.nr_flows")?;
+                at_least_one("packets.nr_flows", nr_flows)?;
+                return Ok(PacketsConfig {
+                    rate: arrivals.as_ref().map_or(0.0, |arrivals| arrivals.mean_rate(duration)),
+                    nr_packets: get_usize(json, "packets.nr_packets")?,
+                    nr_flows,
+                    flows: FlowPopularity::from_json(json, nr_flows)?,
+                    arrivals,
+                    trace: None,
+                });
+            },
+            Some(file) => file,
+        };
+
+        let trace: Arc<Trace> = Arc::new(Trace::from_file(&file).map_err(|e| ConfigError::invalid("packets.trace", e))?);
+        let nr_packets: usize = match get_opt_usize(json, "packets.nr_packets")? {
+            None => trace.records.len(),
+            Some(nr_packets) => nr_packets.min(trace.records.len()),
+        };
+
+        Ok(PacketsConfig {
+            rate: trace.rate(),
+            nr_packets,
+            nr_flows: trace.nr_flows(),
+            flows: FlowPopularity::Uniform { nr_flows: trace.nr_flows() },
+            arrivals: Some(trace.clone()),
+            trace: Some(trace),
+        })
+    }
+}
+
+pub fn at_least_one(path: &str, value: usize) -> Result<(), ConfigError> {
+    if value == 0 {
+        return Err(ConfigError::invalid(path, "should be at least 1".to_string()));
+    }
+    Ok(())
+}
+
+// Helpers to read the fields, 'path' is the dotted JSON path (e.g. "packets.rate"), where a number indexes an array
+// (e.g. "classes.0.ratio")
+fn find<'a>(json: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.').try_fold(json, |json, key| match json {
+        Json::Array(values) => values.get(key.parse::<usize>().ok()?),
+        _ => json.find(key),
+    })
+}
+
+fn describe(json: &Json) -> String {
+    match json {
+        Json::Object(_) => "an object".to_string(),
+        Json::Array(_) => "an array".to_string(),
+        Json::Null => "null".to_string(),
+        _ => json.to_string(),
+    }
+}
+
+fn get_opt_u64(json: &Json, path: &str) -> Result<Option<u64>, ConfigError> {
+    match find(json, path) {
+        None => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(value) => Ok(Some(value)),
+            None => Err(ConfigError::Type { path: path.to_string(), expected: "an unsigned integer", found: describe(value) }),
+        },
+    }
+}
+
+pub(crate) fn get_opt_usize(json: &Json, path: &str) -> Result<Option<usize>, ConfigError> {
+    Ok(get_opt_u64(json, path)?.map(|value| value as usize))
+}
+
+pub(crate) fn get_usize(json: &Json, path: &str) -> Result<usize, ConfigError> {
+    get_opt_usize(json, path)?.ok_or(ConfigError::Missing { path: path.to_string(), expected: "an unsigned integer" })
+}
+
+pub(crate) fn get_opt_f64(json: &Json, path: &str) -> Result<Option<f64>, ConfigError> {
+    match find(json, path) {
+        None => Ok(None),
+        Some(value) => match value.as_f64() {
+            Some(value) => Ok(Some(value)),
+            None => Err(ConfigError::Type { path: path.to_string(), expected: "a number", found: describe(value) }),
+        },
+    }
+}
+
+pub(crate) fn get_f64(json: &Json, path: &str) -> Result<f64, ConfigError> {
+    get_opt_f64(json, path)?.ok_or(ConfigError::Missing { path: path.to_string(), expected: "a number" })
+}
+
+pub(crate) fn get_opt_string(json: &Json, path: &str) -> Result<Option<String>, ConfigError> {
+    match find(json, path) {
+        None => Ok(None),
+        Some(value) => match value.as_string() {
+            Some(value) => Ok(Some(value.to_string())),
+            None => Err(ConfigError::Type { path: path.to_string(), expected: "a string", found: describe(value) }),
+        },
+    }
+}
+
+pub(crate) fn get_opt_bool(json: &Json, path: &str) -> Result<Option<bool>, ConfigError> {
+    match find(json, path) {
+        None => Ok(None),
+        Some(value) => match value.as_boolean() {
+            Some(value) => Ok(Some(value)),
+            None => Err(ConfigError::Type { path: path.to_string(), expected: "a boolean", found: describe(value) }),
+        },
+    }
+}
+
+pub(crate) fn get_string(json: &Json, path: &str) -> Result<String, ConfigError> {
+    get_opt_string(json, path)?.ok_or(ConfigError::Missing { path: path.to_string(), expected: "a string" })
+}
+
+fn get_opt_distribution(json: &Json, section: &str) -> Result<Option<Arc<dyn Distribution>>, ConfigError> {
+    match find(json, section) {
+        None => Ok(None),
+        Some(_) => distribution::from_json(json, section).map(Some),
+    }
+}
+
+pub(crate) fn get_f64_array(json: &Json, path: &str) -> Result<Vec<f64>, ConfigError> {
+    let expected: &'static str = "an array of numbers";
+    match find(json, path) {
+        None => Err(ConfigError::Missing { path: path.to_string(), expected }),
+        Some(Json::Array(values)) => values.iter().map(|value| {
+            value.as_f64().ok_or(ConfigError::Type { path: path.to_string(), expected, found: describe(value) })
+        }).collect(),
+        Some(value) => Err(ConfigError::Type { path: path.to_string(), expected, found: describe(value) }),
+    }
+}
@@ -0,0 +1,307 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    collections::VecDeque,
+    fmt,
+    sync::Arc,
+};
+use rustc_serialize::json::Json;
+
+use crate::{
+    request::Request,
+    config::{
+        ConfigError,
+        get_opt_string,
+        get_opt_usize,
+        at_least_one,
+    },
+};
+
+// Quick Explanation of this
+// How a core picks the next request of its local queue, and whether it preempts the running one.
+// Every core has its own 'QueueDiscipline', chosen by "discipline.policy" (and "network_discipline.policy" for the
+// forwarder and the network cores, FCFS without it). A preemptive discipline only decides at the
+// end of each quantum of the running request: the preempted request goes back to the local queue with the ticks
+// left of its stage, and the core spends "context_switch" ticks before running the next one.
+// A new discipline only needs its type and an entry in 'DISCIPLINES'.
+
+pub trait QueueDiscipline: fmt::Debug + Send + Sync {
+    // Index in 'queue' (not empty) of the next request to run, 'remaining' giving the ticks left of a request in the core
+    fn select(&self, _queue: &VecDeque<Request>, _remaining: &dyn Fn(&Request) -> usize) -> usize {
+        0
+    }
+
+    // Ticks a request runs before the core decides whether to preempt it (None: it runs to completion)
+    fn quantum(&self) -> Option<usize> {
+        None
+    }
+
+    // Whether 'current' gives the core to 'next' (the selected request of the queue) at the end of its quantum
+    fn preempts(&self, _current: &Request, _next: &Request, _remaining: &dyn Fn(&Request) -> usize) -> bool {
+        true
+    }
+
+    // Ticks lost by the core on each preemption
+    fn context_switch(&self) -> usize {
+        0
+    }
+}
+
+type DisciplineBuilder = fn(&Json, &str) -> Result<Arc<dyn QueueDiscipline>, ConfigError>;
+
+// Parameters (besides "policy"), in the section of the discipline:
+const DISCIPLINES: &[(&str, DisciplineBuilder)] = &[
+    // none: first come, first served, each request runs to completion
+    ("fcfs", |_, _| Ok(Arc::new(Fcfs))),
+    // none: processor sharing, as a round robin with a quantum of one tick and no context-switch cost
+    ("ps", |_, _| Ok(Arc::new(RoundRobin { quantum: 1, context_switch: 0 }))),
+    // quantum and context_switch (optional, 0 by default)
+    ("rr", |json, section| Ok(Arc::new(RoundRobin {
+        quantum: required_quantum(json, section)?,
+        context_switch: context_switch(json, section)?,
+    }))),
+    // quantum (how often a shorter request may preempt the running one) and context_switch
+    ("srpt", |json, section| Ok(Arc::new(Srpt {
+        quantum: required_quantum(json, section)?,
+        context_switch: context_switch(json, section)?,
+    }))),
+    // quantum (optional) and context_switch, the priority of each class is in "classes.<i>.priority" (0 by default,
+    // the bigger first)
+    ("priority", |json, section| {
+        let nr_classes: usize = match json.find("classes") {
+            Some(Json::Array(classes)) => classes.len(),
+            _ => 0,
+        };
+        let priorities: Vec<usize> = (0..nr_classes).map(|i| Ok(get_opt_usize(json, &format!("classes.{}.priority", i))?.unwrap_or(0))).collect::<Result<Vec<usize>, ConfigError>>()?;
+        Ok(Arc::new(Priority {
+            priorities,
+            quantum: quantum(json, section)?,
+            context_switch: context_switch(json, section)?,
+        }))
+    }),
+];
+
+// The discipline of 'section', FCFS when it is missing
+pub fn from_json(json: &Json, section: &str) -> Result<Arc<dyn QueueDiscipline>, ConfigError> {
+    if json.find(section).is_none() {
+        return Ok(Arc::new(Fcfs));
+    }
+
+    let path: String = format!("{}.policy", section);
+    let name: String = get_opt_string(json, &path)?.unwrap_or("fcfs".to_string());
+    match DISCIPLINES.iter().find(|(n, _)| *n == name) {
+        Some((_, builder)) => builder(json, section),
+        None => {
+            let names: Vec<&str> = DISCIPLINES.iter().map(|(n, _)| *n).collect();
+            Err(ConfigError::invalid(&path, format!("should be one of {}, found \"{}\"", names.join(", "), name)))
+        },
+    }
+}
+
+fn quantum(json: &Json, section: &str) -> Result<Option<usize>, ConfigError> {
+    let path: String = format!("{}.quantum", section);
+    match get_opt_usize(json, &path)? {
+        None => Ok(None),
+        Some(quantum) => {
+            at_least_one(&path, quantum)?;
+            Ok(Some(quantum))
+        },
+    }
+}
+
+fn required_quantum(json: &Json, section: &str) -> Result<usize, ConfigError> {
+    quantum(json, section)?.ok_or(ConfigError::Missing { path: format!("{}.quantum", section), expected: "an unsigned integer" })
+}
+
+fn context_switch(json: &Json, section: &str) -> Result<usize, ConfigError> {
+    Ok(get_opt_usize(json, &format!("{}.context_switch", section))?.unwrap_or(0))
+}
+
+// Structure
+#[derive(Debug)]
+pub struct Fcfs;
+
+impl QueueDiscipline for Fcfs {}
+
+#[derive(Debug)]
+pub struct RoundRobin {
+    pub quantum: usize,
+    pub context_switch: usize,
+}
+
+impl QueueDiscipline for RoundRobin {
+    fn quantum(&self) -> Option<usize> {
+        Some(self.quantum)
+    }
+
+    fn context_switch(&self) -> usize {
+        self.context_switch
+    }
+}
+
+#[derive(Debug)]
+pub struct Srpt {
+    pub quantum: usize,
+    pub context_switch: usize,
+}
+
+impl QueueDiscipline for Srpt {
+    fn select(&self, queue: &VecDeque<Request>, remaining: &dyn Fn(&Request) -> usize) -> usize {
+        // The first one among the shortest
+        (0..queue.len()).min_by_key(|i| remaining(&queue[*i])).unwrap_or(0)
+    }
+
+    fn quantum(&self) -> Option<usize> {
+        Some(self.quantum)
+    }
+
+    fn preempts(&self, current: &Request, next: &Request, remaining: &dyn Fn(&Request) -> usize) -> bool {
+        remaining(next) < remaining(current)
+    }
+
+    fn context_switch(&self) -> usize {
+        self.context_switch
+    }
+}
+
+#[derive(Debug)]
+pub struct Priority {
+    // By class
+    pub priorities: Vec<usize>,
+    pub quantum: Option<usize>,
+    pub context_switch: usize,
+}
+
+impl Priority {
+    fn priority(&self, req: &Request) -> usize {
+        self.priorities.get(req.get_request_class()).cloned().unwrap_or(0)
+    }
+}
+
+impl QueueDiscipline for Priority {
+    fn select(&self, queue: &VecDeque<Request>, _remaining: &dyn Fn(&Request) -> usize) -> usize {
+        // The first one among the highest priority
+        (0..queue.len()).rev().max_by_key(|i| self.priority(&queue[*i])).unwrap_or(0)
+    }
+
+    fn quantum(&self) -> Option<usize> {
+        self.quantum
+    }
+
+    fn preempts(&self, current: &Request, next: &Request, _remaining: &dyn Fn(&Request) -> usize) -> bool {
+        self.priority(next) > self.priority(current)
+    }
+
+    fn context_switch(&self) -> usize {
+        self.context_switch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CoreAction,
+        CoreState,
+        event::EventQueue,
+        worker_core::Core,
+    };
+
+    use super::*;
+
+    // Requests with these application times and classes, in this order, done with the network stack
+    fn queue(requests: &[(usize, usize)]) -> VecDeque<Request> {
+        requests.iter().enumerate().map(|(id, (request_time, class))| {
+            let mut req: Request = Request::new(id, 0, *class, 0, 1, 1, *request_time);
+            req.set_p_start(0);
+            req.p_schedule(0);
+            req
+        }).collect()
+    }
+
+    fn select(discipline: &dyn QueueDiscipline, requests: &[(usize, usize)]) -> usize {
+        discipline.select(&queue(requests), &|req| req.get_request_time())
+    }
+
+    #[test]
+    fn quantum_is_required_to_preempt() {
+        let json: Json = Json::from_str(r#"{"discipline": {"policy": "srpt"}, "network_discipline": {"policy": "rr"}}"#).unwrap();
+        for section in ["discipline", "network_discipline"] {
+            match from_json(&json, section) {
+                Err(ConfigError::Missing { path, .. }) => assert_eq!(path, format!("{}.quantum", section)),
+                other => panic!("{}: expected a missing quantum, found {:?}", section, other),
+            }
+        }
+        // Without its section, a core runs each request to completion
+        assert_eq!(format!("{:?}", from_json(&json, "other_discipline").unwrap()), "Fcfs");
+    }
+
+    #[test]
+    fn select_order() {
+        let requests: [(usize, usize); 4] = [(300, 0), (100, 1), (200, 2), (100, 2)];
+        assert_eq!(select(&Fcfs, &requests), 0);
+        assert_eq!(select(&RoundRobin { quantum: 10, context_switch: 0 }, &requests), 0);
+        // The first one among the shortest
+        assert_eq!(select(&Srpt { quantum: 10, context_switch: 0 }, &requests), 1);
+        // The first one among the highest priority, a class without priority having 0
+        let priority: Priority = Priority { priorities: vec![1, 0, 2], quantum: None, context_switch: 0 };
+        assert_eq!(select(&priority, &requests), 2);
+        assert_eq!(select(&priority, &[(300, 1), (100, 3)]), 0);
+    }
+
+    #[test]
+    fn preempts_only_for_a_better_request() {
+        let requests: VecDeque<Request> = queue(&[(300, 0), (100, 1), (300, 1)]);
+        let remaining = |req: &Request| req.get_request_time();
+        let srpt: Srpt = Srpt { quantum: 10, context_switch: 0 };
+        assert!(srpt.preempts(&requests[0], &requests[1], &remaining));
+        assert!(!srpt.preempts(&requests[0], &requests[2], &remaining));
+        assert!(!srpt.preempts(&requests[1], &requests[0], &remaining));
+        let priority: Priority = Priority { priorities: vec![0, 1], quantum: Some(10), context_switch: 0 };
+        assert!(priority.preempts(&requests[0], &requests[1], &remaining));
+        assert!(!priority.preempts(&requests[1], &requests[2], &remaining));
+    }
+
+    // Runs the requests (arrival tick and application time) on an application core, which sees each one in the tick
+    // after its arrival. Returns the departure of each one, by id, and the number of preemptions.
+    fn completions(discipline: Arc<dyn QueueDiscipline>, jobs: &[(usize, usize)]) -> (Vec<usize>, usize) {
+        let mut core: Core = Core::new(0, CoreAction::Application, jobs.len(), discipline);
+        let mut events: EventQueue = EventQueue::new();
+        let mut requests: VecDeque<Request> = queue(&jobs.iter().map(|(_, request_time)| (*request_time, 0)).collect::<Vec<(usize, usize)>>());
+
+        let mut t_departures: Vec<usize> = vec![0; jobs.len()];
+        let mut nr_departed: usize = 0;
+        let mut t_cur: usize = 0;
+        while nr_departed < jobs.len() {
+            if let CoreState::Finished(req) = core.schedule(t_cur, None, &mut events) {
+                // The stage keeps its time, only the ticks left of it change with the preemptions
+                assert_eq!(req.get_request_time(), jobs[req.get_id()].1);
+                t_departures[req.get_id()] = req.get_departure_time();
+                nr_departed += 1;
+            }
+            while requests.front().is_some_and(|req| jobs[req.get_id()].0 == t_cur) {
+                core.try_enqueue(requests.pop_front().unwrap(), t_cur, &mut events).unwrap();
+            }
+            t_cur += 1;
+        }
+        (t_departures, core.get_stats().preemptions)
+    }
+
+    #[test]
+    fn two_jobs_completion_times() {
+        let jobs: [(usize, usize); 2] = [(0, 6), (0, 4)];
+        // The first one runs the ticks 1 to 6 and departs in the next one, then the second one runs the ticks 7 to 10
+        assert_eq!(completions(Arc::new(Fcfs), &jobs), (vec![7, 11], 0));
+        // They take turns from the tick 1 to 6, then the second one keeps the tick 7, its last one, instead of being
+        // preempted, and the first one runs its 3 ticks left
+        assert_eq!(completions(Arc::new(RoundRobin { quantum: 1, context_switch: 0 }), &jobs), (vec![11, 8], 5));
+        // The ticks 1 to 4 for the first one, 5 to 8 for the second one, and 9 and 10 for the first one
+        assert_eq!(completions(Arc::new(RoundRobin { quantum: 4, context_switch: 0 }), &jobs), (vec![11, 9], 1));
+        // The same, with the ticks 5 to 7 lost in the context switch
+        assert_eq!(completions(Arc::new(RoundRobin { quantum: 4, context_switch: 3 }), &jobs), (vec![14, 12], 1));
+        // The shortest one runs first
+        assert_eq!(completions(Arc::new(Srpt { quantum: 2, context_switch: 0 }), &jobs), (vec![11, 5], 0));
+        // A shorter one arriving in the tick 2 preempts the first one at the end of its quantum (tick 3)
+        assert_eq!(completions(Arc::new(Srpt { quantum: 2, context_switch: 0 }), &[(0, 6), (2, 3)]), (vec![10, 6], 1));
+    }
+}
@@ -0,0 +1,228 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::rand::Rng;
+use rustc_serialize::json::Json;
+
+use crate::{
+    request::Request,
+    config::{
+        ConfigError,
+        get_opt_string,
+        get_usize,
+        at_least_one,
+    },
+    layout::Context,
+    worker_core::Core,
+    results::DropStage,
+};
+
+// Quick Explanation of this
+// The dispatcher of the centralized layouts: the forwarder in Layout 1 and the network core in Layout 3 hand each
+// request to a worker chosen by "dispatcher.policy". A request that no worker can take waits in the ready queue of
+// the dispatching core (the dispatcher buffer, of "queue_size" requests, the new ones are dropped when it is full),
+// and is dispatched as soon as a worker can take it, in arrival order. Or else, by "dispatcher.when_busy", the
+// dispatching core holds it and retries in the next rounds, or it is dropped right away. A worker never takes more
+// requests than its local queue holds.
+// The dispatcher sees the workers as they were at the start of the round (before they make progress), so a worker
+// that completes a request is only seen idle in the next round.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DispatchPolicy {
+    // The first idle worker, starting from the last one that received a request (default)
+    FirstIdle,
+    // The next worker after the last one that received a request
+    RoundRobin,
+    // Any worker
+    Random,
+    // Join the shortest queue: the worker with the fewest requests (running or waiting)
+    Jsq,
+    // The shortest of two workers drawn at random
+    PowerOfTwo,
+    // JSQ among the workers with fewer than 'k' requests (bounded queues, as in Shinjuku and Perséphone)
+    Jbsq(usize),
+}
+
+// What happens to a request that no worker can take
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BusyPolicy {
+    // It waits in the dispatcher buffer, while the dispatching core goes on (default)
+    Buffer,
+    // The dispatching core holds it, doing nothing else till a worker takes it
+    Retry,
+    // It is dropped
+    Drop,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DispatcherConfig {
+    pub policy: DispatchPolicy,
+    pub when_busy: BusyPolicy,
+}
+
+type PolicyBuilder = fn(&Json) -> Result<DispatchPolicy, ConfigError>;
+
+// Parameters (besides "policy"), in "dispatcher":
+const POLICIES: &[(&str, PolicyBuilder)] = &[
+    // none
+    ("first_idle", |_| Ok(DispatchPolicy::FirstIdle)),
+    // none
+    ("round_robin", |_| Ok(DispatchPolicy::RoundRobin)),
+    // none
+    ("random", |_| Ok(DispatchPolicy::Random)),
+    // none
+    ("jsq", |_| Ok(DispatchPolicy::Jsq)),
+    // none
+    ("power_of_two", |_| Ok(DispatchPolicy::PowerOfTwo)),
+    // k, the bound of requests per worker
+    ("jbsq", |json| {
+        let k: usize = get_usize(json, "dispatcher.k")?;
+        at_least_one("dispatcher.k", k)?;
+        Ok(DispatchPolicy::Jbsq(k))
+    }),
+];
+
+impl DispatcherConfig {
+    // First idle, buffering, when the section is missing
+    pub fn from_json(json: &Json) -> Result<DispatcherConfig, ConfigError> {
+        let name: String = get_opt_string(json, "dispatcher.policy")?.unwrap_or("first_idle".to_string());
+        let policy: DispatchPolicy = match POLICIES.iter().find(|(n, _)| *n == name) {
+            Some((_, builder)) => builder(json)?,
+            None => {
+                let names: Vec<&str> = POLICIES.iter().map(|(n, _)| *n).collect();
+                return Err(ConfigError::invalid("dispatcher.policy", format!("should be one of {}, found \"{}\"", names.join(", "), name)));
+            },
+        };
+        let when_busy: BusyPolicy = match get_opt_string(json, "dispatcher.when_busy")?.as_deref() {
+            None | Some("buffer") => BusyPolicy::Buffer,
+            Some("retry") => BusyPolicy::Retry,
+            Some("drop") => BusyPolicy::Drop,
+            Some(other) => return Err(ConfigError::invalid("dispatcher.when_busy", format!("should be \"buffer\", \"retry\" or \"drop\", found \"{}\"", other))),
+        };
+
+        Ok(DispatcherConfig {
+            policy,
+            when_busy,
+        })
+    }
+}
+
+// What the dispatcher knows of a worker
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerView {
+    pub is_idle: bool,
+    // Running and waiting requests
+    pub load: usize,
+}
+
+// Structure
+pub struct Dispatcher {
+    policy: DispatchPolicy,
+    when_busy: BusyPolicy,
+    // Recorded for the requests dropped by the dispatcher
+    drop_stage: DropStage,
+    last_worker_idx: usize,
+}
+
+// Associate Functions
+impl Dispatcher {
+    pub fn new(config: DispatcherConfig, drop_stage: DropStage) -> Dispatcher {
+        Dispatcher {
+            policy: config.policy,
+            when_busy: config.when_busy,
+            drop_stage,
+            last_worker_idx: 0,
+        }
+    }
+
+    // Whether the dispatching core is holding a request that no worker took yet (so it must not make progress)
+    pub fn is_blocked(&self, dispatcher: &Core) -> bool {
+        self.when_busy == BusyPolicy::Retry && !dispatcher.is_ready_queue_empty()
+    }
+
+    pub fn get_drop_stage(&self) -> DropStage {
+        self.drop_stage
+    }
+
+    // The last worker that received a request
+    pub fn get_last_worker_idx(&self) -> usize {
+        self.last_worker_idx
+    }
+
+    pub fn snapshot(workers: &[Core]) -> Vec<WorkerView> {
+        workers.iter().map(|core| WorkerView { is_idle: core.is_idle(), load: core.get_load() }).collect()
+    }
+
+    // Workers that may take a request, in the order they are tried
+    fn candidates(&self, views: &[WorkerView], workers: &[Core]) -> Vec<usize> {
+        let n: usize = workers.len();
+        let start: usize = match self.policy {
+            DispatchPolicy::FirstIdle => self.last_worker_idx,
+            _ => self.last_worker_idx + 1,
+        };
+        (0..n).map(|i| (start + i) % n)
+            .filter(|idx| !workers[*idx].is_full())
+            .filter(|idx| match self.policy {
+                DispatchPolicy::FirstIdle => views[*idx].is_idle,
+                DispatchPolicy::Jbsq(k) => views[*idx].load < k,
+                _ => true,
+            })
+            .collect()
+    }
+
+    // The worker of the next request, if any can take it
+    fn select(&self, views: &[WorkerView], workers: &[Core], ctx: &mut Context) -> Option<usize> {
+        let candidates: Vec<usize> = self.candidates(views, workers);
+        if candidates.is_empty() {
+            return None;
+        }
+        let idx: usize = match self.policy {
+            DispatchPolicy::FirstIdle | DispatchPolicy::RoundRobin => candidates[0],
+            DispatchPolicy::Random => candidates[ctx.rng.gen_range(0..candidates.len())],
+            // 'min_by_key' keeps the first one among the shortest
+            DispatchPolicy::Jsq | DispatchPolicy::Jbsq(_) => *candidates.iter().min_by_key(|idx| views[**idx].load).unwrap(),
+            DispatchPolicy::PowerOfTwo => {
+                let first: usize = ctx.rng.gen_range(0..candidates.len());
+                let second: usize = match candidates.len() {
+                    1 => first,
+                    n => (first + ctx.rng.gen_range(1..n)) % n,
+                };
+                let (a, b): (usize, usize) = (candidates[first], candidates[second]);
+                if views[b].load < views[a].load { b } else { a }
+            },
+        };
+        Some(idx)
+    }
+
+    // Hands the requests of the ready queue of 'dispatcher' to the workers ('views' is their state at the start of
+    // the round), as long as a worker can take the next one
+    pub fn dispatch(&mut self, dispatcher: &mut Core, workers: &mut [Core], mut views: Vec<WorkerView>, ctx: &mut Context) {
+        let was_blocked: bool = self.is_blocked(dispatcher);
+        while !dispatcher.is_ready_queue_empty() {
+            let idx: usize = match self.select(&views, workers, ctx) {
+                Some(idx) => idx,
+                None => break,
+            };
+            self.last_worker_idx = idx;
+            let req: Request = dispatcher.pop_ready_queue();
+            workers[idx].try_enqueue(req, ctx.t_cur, ctx.events).expect("ERROR: should not be here.");
+            views[idx].is_idle = false;
+            views[idx].load += 1;
+        }
+
+        if self.when_busy == BusyPolicy::Drop {
+            while !dispatcher.is_ready_queue_empty() {
+                let req: Request = dispatcher.pop_ready_queue();
+                ctx.drop(dispatcher, req, self.drop_stage);
+            }
+        } else if !dispatcher.is_ready_queue_empty() {
+            // The workers as they are now are seen in the next round, which may take the requests still waiting
+            if !self.candidates(&Dispatcher::snapshot(workers), workers).is_empty() {
+                dispatcher.wake_up(ctx.t_cur, ctx.events);
+            }
+        } else if was_blocked {
+            // The dispatching core goes on in the next round
+            dispatcher.wake_up(ctx.t_cur, ctx.events);
+        }
+    }
+}
@@ -0,0 +1,577 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fmt,
+    fs,
+    sync::Arc,
+};
+use ::rand::{
+    rngs::SmallRng,
+    Rng,
+};
+use rustc_serialize::json::Json;
+
+use crate::config::{
+    ConfigError,
+    get_f64,
+    get_f64_array,
+    get_opt_string,
+    get_string,
+    get_usize,
+};
+
+// Quick Explanation of this
+// The number of ticks used by a stage (forwarder, network stack or application) is drawn from a 'Distribution'.
+// Each distribution is read from the section of its stage (e.g. "application": {"distribution": "lognormal", ...}),
+// and most of them are parameterized by their mean ("mean1"), so changing the distribution keeps the load.
+// A new distribution only needs its type and an entry in 'DISTRIBUTIONS'.
+
+pub trait Distribution: fmt::Debug + Send + Sync {
+    // One sample, in ticks (the caller truncates it)
+    fn sample(&self, rng: &mut SmallRng) -> f64;
+}
+
+type DistributionBuilder = fn(&Json, &str) -> Result<Arc<dyn Distribution>, ConfigError>;
+
+// Parameters (besides "distribution"), in the section of the stage:
+const DISTRIBUTIONS: &[(&str, DistributionBuilder)] = &[
+    // mean1
+    ("constant", |json, section| Ok(Arc::new(Constant { value: non_negative(json, section, "mean1")? }))),
+    // mean1
+    ("exponential", |json, section| Ok(Arc::new(Exponential { lambda: 1.0 / positive(json, section, "mean1")? }))),
+    // mean1, mean2 and mode (probability of mean1)
+    ("bimodal", |json, section| Ok(Arc::new(Bimodal {
+        mean1: non_negative(json, section, "mean1")?,
+        mean2: non_negative(json, section, "mean2")?,
+        mode: probability(json, section, "mode")?,
+    }))),
+    // min and max
+    ("uniform", |json, section| {
+        let min: f64 = non_negative(json, section, "min")?;
+        let max: f64 = non_negative(json, section, "max")?;
+        if max < min {
+            return Err(ConfigError::invalid(&format!("{}.max", section), format!("should be at least min ({}), found {}", min, max)));
+        }
+        Ok(Arc::new(Uniform { min, max }))
+    }),
+    // mean1 and sigma (standard deviation of the underlying normal distribution)
+    ("lognormal", |json, section| {
+        let mean: f64 = positive(json, section, "mean1")?;
+        let sigma: f64 = non_negative(json, section, "sigma")?;
+        Ok(Arc::new(LogNormal { mu: mean.ln() - sigma * sigma / 2.0, sigma }))
+    }),
+    // mean1 and shape (alpha, bigger than 1 so the mean exists)
+    ("pareto", |json, section| {
+        let mean: f64 = positive(json, section, "mean1")?;
+        let shape: f64 = get_f64(json, &format!("{}.shape", section))?;
+        if shape <= 1.0 {
+            return Err(ConfigError::invalid(&format!("{}.shape", section), format!("should be bigger than 1, found {}", shape)));
+        }
+        Ok(Arc::new(Pareto { scale: mean * (shape - 1.0) / shape, shape }))
+    }),
+    // mean1 and shape (k)
+    ("weibull", |json, section| {
+        let mean: f64 = positive(json, section, "mean1")?;
+        let shape: f64 = positive(json, section, "shape")?;
+        Ok(Arc::new(Weibull { scale: mean / gamma(1.0 + 1.0 / shape), shape }))
+    }),
+    // mean1 and shape (k)
+    ("gamma", |json, section| {
+        let mean: f64 = positive(json, section, "mean1")?;
+        let shape: f64 = positive(json, section, "shape")?;
+        Ok(Arc::new(Gamma { shape, scale: mean / shape }))
+    }),
+    // mean1 and shape (number of phases, an unsigned integer)
+    ("erlang", |json, section| {
+        let mean: f64 = positive(json, section, "mean1")?;
+        let shape: usize = get_usize(json, &format!("{}.shape", section))?;
+        crate::config::at_least_one(&format!("{}.shape", section), shape)?;
+        Ok(Arc::new(Gamma { shape: shape as f64, scale: mean / shape as f64 }))
+    }),
+    // means and probabilities (one per phase)
+    ("hyperexponential", |json, section| {
+        let means: Vec<f64> = get_f64_array(json, &format!("{}.means", section))?;
+        let probabilities: Vec<f64> = get_f64_array(json, &format!("{}.probabilities", section))?;
+        if means.is_empty() || means.len() != probabilities.len() {
+            return Err(ConfigError::invalid(&format!("{}.probabilities", section), format!("should have one probability per mean ({}), found {}", means.len(), probabilities.len())));
+        }
+        if let Some(mean) = means.iter().find(|mean| **mean <= 0.0) {
+            return Err(ConfigError::invalid(&format!("{}.means", section), format!("should be bigger than 0, found {}", mean)));
+        }
+        if probabilities.iter().any(|p| !(0.0..=1.0).contains(p)) || (probabilities.iter().sum::<f64>() - 1.0).abs() > 1e-6 {
+            return Err(ConfigError::invalid(&format!("{}.probabilities", section), "should be between 0 and 1 and sum to 1".to_string()));
+        }
+        Ok(Arc::new(HyperExponential { means, probabilities }))
+    }),
+    // file and format ("samples" or "cdf"), see 'Empirical'
+    ("empirical", |json, section| {
+        let path: String = format!("{}.file", section);
+        let file: String = get_string(json, &path)?;
+        let data: String = fs::read_to_string(&file).map_err(|e| ConfigError::invalid(&path, format!("cannot read {}: {}", file, e)))?;
+        let empirical: Empirical = match get_opt_string(json, &format!("{}.format", section))?.as_deref() {
+            None | Some("samples") => Empirical::from_samples(&data),
+            Some("cdf") => Empirical::from_cdf(&data),
+            Some(other) => return Err(ConfigError::invalid(&format!("{}.format", section), format!("should be \"samples\" or \"cdf\", found \"{}\"", other))),
+        }.map_err(|e| ConfigError::invalid(&path, format!("{}: {}", file, e)))?;
+        Ok(Arc::new(empirical))
+    }),
+];
+
+pub fn from_json(json: &Json, section: &str) -> Result<Arc<dyn Distribution>, ConfigError> {
+    let path: String = format!("{}.distribution", section);
+    let name: String = get_string(json, &path)?;
+
+    match DISTRIBUTIONS.iter().find(|(n, _)| *n == name) {
+        Some((_, builder)) => builder(json, section),
+        None => {
+            let names: Vec<&str> = DISTRIBUTIONS.iter().map(|(n, _)| *n).collect();
+            Err(ConfigError::invalid(&path, format!("should be one of {}, found \"{}\"", names.join(", "), name)))
+        },
+    }
+}
+
+fn non_negative(json: &Json, section: &str, field: &str) -> Result<f64, ConfigError> {
+    let path: String = format!("{}.{}", section, field);
+    let value: f64 = get_f64(json, &path)?;
+    if value < 0.0 {
+        return Err(ConfigError::invalid(&path, format!("should not be negative, found {}", value)));
+    }
+    Ok(value)
+}
+
+fn positive(json: &Json, section: &str, field: &str) -> Result<f64, ConfigError> {
+    let path: String = format!("{}.{}", section, field);
+    let value: f64 = get_f64(json, &path)?;
+    if value <= 0.0 {
+        return Err(ConfigError::invalid(&path, format!("should be bigger than 0, found {}", value)));
+    }
+    Ok(value)
+}
+
+fn probability(json: &Json, section: &str, field: &str) -> Result<f64, ConfigError> {
+    let path: String = format!("{}.{}", section, field);
+    let value: f64 = get_f64(json, &path)?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err(ConfigError::invalid(&path, format!("should be between 0 and 1, found {}", value)));
+    }
+    Ok(value)
+}
+
+// Structure
+#[derive(Debug)]
+pub struct Constant {
+    pub value: f64,
+}
+
+impl Distribution for Constant {
+    fn sample(&self, _rng: &mut SmallRng) -> f64 {
+        self.value
+    }
+}
+
+#[derive(Debug)]
+pub struct Exponential {
+    pub lambda: f64,
+}
+
+impl Distribution for Exponential {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        exponential(rng, self.lambda)
+    }
+}
+
+#[derive(Debug)]
+pub struct Bimodal {
+    pub mean1: f64,
+    pub mean2: f64,
+    // Probability of mean1
+    pub mode: f64,
+}
+
+impl Distribution for Bimodal {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        if rng.gen::<f64>() < self.mode {
+            self.mean1
+        } else {
+            self.mean2
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Uniform {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Distribution for Uniform {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        self.min + rng.gen::<f64>() * (self.max - self.min)
+    }
+}
+
+#[derive(Debug)]
+pub struct LogNormal {
+    pub mu: f64,
+    pub sigma: f64,
+}
+
+impl Distribution for LogNormal {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        (self.mu + self.sigma * standard_normal(rng)).exp()
+    }
+}
+
+#[derive(Debug)]
+pub struct Pareto {
+    pub scale: f64,
+    pub shape: f64,
+}
+
+impl Distribution for Pareto {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        self.scale / (1.0 - rng.gen::<f64>()).powf(1.0 / self.shape)
+    }
+}
+
+#[derive(Debug)]
+pub struct Weibull {
+    pub scale: f64,
+    pub shape: f64,
+}
+
+impl Distribution for Weibull {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        self.scale * (-(1.0 - rng.gen::<f64>()).ln()).powf(1.0 / self.shape)
+    }
+}
+
+// Erlang is a gamma with an integer shape
+#[derive(Debug)]
+pub struct Gamma {
+    pub shape: f64,
+    pub scale: f64,
+}
+
+impl Distribution for Gamma {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        self.scale * standard_gamma(rng, self.shape)
+    }
+}
+
+#[derive(Debug)]
+pub struct HyperExponential {
+    pub means: Vec<f64>,
+    pub probabilities: Vec<f64>,
+}
+
+impl Distribution for HyperExponential {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        let r: f64 = rng.gen::<f64>();
+        let mut acc: f64 = 0.0;
+        let mut phase: usize = self.means.len() - 1;
+        for (i, p) in self.probabilities.iter().enumerate() {
+            acc += p;
+            if r < acc {
+                phase = i;
+                break;
+            }
+        }
+        exponential(rng, 1.0 / self.means[phase])
+    }
+}
+
+// Measured service times, sampled by inverse transform with linear interpolation between the points of the CDF.
+// The file is either:
+// - "samples": one value per line (e.g. a .dat file), or
+// - "cdf": lines with the value in the first column and its cumulative probability in the last one,
+//   as written by 'script_cdf.sh' (value count cdf). The probabilities are normalized by the last one.
+// Columns may be separated by spaces or commas, and empty lines and lines starting with '#' are ignored.
+#[derive(Debug)]
+pub struct Empirical {
+    // Sorted values and their cumulative probabilities (the last one is 1)
+    pub values: Vec<f64>,
+    pub cdf: Vec<f64>,
+}
+
+impl Empirical {
+    pub fn from_samples(data: &str) -> Result<Empirical, String> {
+        let mut values: Vec<f64> = Vec::<f64>::new();
+        for (line, columns) in rows(data) {
+            values.push(parse_column(line, columns[0])?);
+        }
+        if values.is_empty() {
+            return Err("there is no sample".to_string());
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        // The i-th smallest sample is the quantile i/(n-1)
+        let n: usize = values.len();
+        let cdf: Vec<f64> = (0..n).map(|i| if n == 1 { 1.0 } else { i as f64 / (n - 1) as f64 }).collect();
+
+        Ok(Empirical { values, cdf })
+    }
+
+    pub fn from_cdf(data: &str) -> Result<Empirical, String> {
+        let mut values: Vec<f64> = Vec::<f64>::new();
+        let mut cdf: Vec<f64> = Vec::<f64>::new();
+        for (line, columns) in rows(data) {
+            if columns.len() < 2 {
+                return Err(format!("line {}: expected the value and its cumulative probability", line));
+            }
+            let value: f64 = parse_column(line, columns[0])?;
+            let p: f64 = parse_column(line, columns[columns.len() - 1])?;
+            if let (Some(last_value), Some(last_p)) = (values.last(), cdf.last()) {
+                if value < *last_value || p < *last_p {
+                    return Err(format!("line {}: the values and the probabilities should not decrease", line));
+                }
+            }
+            values.push(value);
+            cdf.push(p);
+        }
+
+        // A single value (a point mass) has a cumulative probability of 0 in the output of 'script_cdf.sh'
+        if values.len() == 1 {
+            cdf[0] = 1.0;
+        }
+        let total: f64 = match cdf.last() {
+            None => return Err("there is no point".to_string()),
+            Some(total) if *total <= 0.0 => return Err("the last cumulative probability should be bigger than 0".to_string()),
+            Some(total) => *total,
+        };
+        for p in cdf.iter_mut() {
+            *p /= total;
+        }
+
+        Ok(Empirical { values, cdf })
+    }
+}
+
+impl Distribution for Empirical {
+    fn sample(&self, rng: &mut SmallRng) -> f64 {
+        let u: f64 = rng.gen::<f64>();
+
+        // First point with cdf >= u, interpolating from the previous one
+        let i: usize = self.cdf.partition_point(|p| *p < u);
+        if i == 0 {
+            return self.values[0];
+        }
+        if i == self.values.len() {
+            return self.values[i - 1];
+        }
+        let (p0, p1): (f64, f64) = (self.cdf[i - 1], self.cdf[i]);
+        let (v0, v1): (f64, f64) = (self.values[i - 1], self.values[i]);
+        if p1 == p0 {
+            return v1;
+        }
+        v0 + (v1 - v0) * (u - p0) / (p1 - p0)
+    }
+}
+
+// Non-empty lines that are not comments, as (line number, columns)
+fn rows(data: &str) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    data.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| (i, line.split(|c: char| c == ',' || c.is_whitespace()).filter(|c| !c.is_empty()).collect()))
+}
+
+fn parse_column(line: usize, column: &str) -> Result<f64, String> {
+    match column.parse::<f64>() {
+        Ok(value) if value.is_finite() && value >= 0.0 => Ok(value),
+        _ => Err(format!("line {}: '{}' is not a non-negative number", line, column)),
+    }
+}
+
+// Inverse transform of the exponential distribution
+pub fn exponential(rng: &mut SmallRng, lambda: f64) -> f64 {
+    let l: f64 = -((1.0 - rng.gen::<f64>()).ln());
+    l / lambda
+}
+
+// Box-Muller transform
+fn standard_normal(rng: &mut SmallRng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+// Marsaglia and Tsang's method, with scale 1
+fn standard_gamma(rng: &mut SmallRng, shape: f64) -> f64 {
+    if shape < 1.0 {
+        // Gamma(k) = Gamma(k + 1) * U^(1/k)
+        let u: f64 = 1.0 - rng.gen::<f64>();
+        return standard_gamma(rng, shape + 1.0) * u.powf(1.0 / shape);
+    }
+
+    let d: f64 = shape - 1.0 / 3.0;
+    let c: f64 = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x: f64 = standard_normal(rng);
+        let v: f64 = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = 1.0 - rng.gen::<f64>();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+// Lanczos approximation (g = 7), used to find the scale of the Weibull distribution from its mean
+fn gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula
+        return std::f64::consts::PI / ((std::f64::consts::PI * x).sin() * gamma(1.0 - x));
+    }
+
+    let x: f64 = x - 1.0;
+    let mut a: f64 = COEFFICIENTS[0];
+    let t: f64 = x + 7.5;
+    for (i, c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        a += c / (x + i as f64);
+    }
+    (2.0 * std::f64::consts::PI).sqrt() * t.powf(x + 0.5) * (-t).exp() * a
+}
+
+#[cfg(test)]
+mod tests {
+    use ::rand::SeedableRng;
+
+    use super::*;
+
+    const NR_SAMPLES: usize = 200000;
+
+    fn build(section: &str) -> Result<Arc<dyn Distribution>, ConfigError> {
+        let json: Json = Json::from_str(&format!(r#"{{"application": {}}}"#, section)).unwrap();
+        from_json(&json, "application")
+    }
+
+    fn sample_mean(section: &str) -> f64 {
+        let distribution: Arc<dyn Distribution> = build(section).unwrap();
+        let mut rng: SmallRng = SmallRng::seed_from_u64(1);
+        (0..NR_SAMPLES).map(|_| distribution.sample(&mut rng)).sum::<f64>() / NR_SAMPLES as f64
+    }
+
+    fn assert_mean(section: &str, mean: f64) {
+        let found: f64 = sample_mean(section);
+        assert!((found - mean).abs() < 0.02 * mean, "{}: the sample mean is {}, expected {}", section, found, mean);
+    }
+
+    fn invalid_path(section: &str) -> String {
+        match build(section) {
+            Err(ConfigError::Invalid { path, .. }) => path,
+            other => panic!("{}: expected an invalid parameter, found {:?}", section, other),
+        }
+    }
+
+    #[test]
+    fn gamma_function_matches_known_values() {
+        assert!((gamma(5.0) - 24.0).abs() < 1e-9);
+        assert!((gamma(0.5) - std::f64::consts::PI.sqrt()).abs() < 1e-12);
+        assert!((gamma(1.5) - std::f64::consts::PI.sqrt() / 2.0).abs() < 1e-12);
+        assert!((gamma(3.0) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sample_means_match_the_configured_mean() {
+        assert_mean(r#"{"distribution": "uniform", "min": 500, "max": 1500}"#, 1000.0);
+        assert_mean(r#"{"distribution": "lognormal", "mean1": 1000, "sigma": 1}"#, 1000.0);
+        assert_mean(r#"{"distribution": "pareto", "mean1": 1000, "shape": 3}"#, 1000.0);
+        assert_mean(r#"{"distribution": "weibull", "mean1": 1000, "shape": 2}"#, 1000.0);
+        assert_mean(r#"{"distribution": "weibull", "mean1": 1000, "shape": 0.5}"#, 1000.0);
+        assert_mean(r#"{"distribution": "gamma", "mean1": 1000, "shape": 4}"#, 1000.0);
+        assert_mean(r#"{"distribution": "gamma", "mean1": 1000, "shape": 0.5}"#, 1000.0);
+        assert_mean(r#"{"distribution": "erlang", "mean1": 1000, "shape": 3}"#, 1000.0);
+        assert_mean(r#"{"distribution": "hyperexponential", "means": [500, 5000], "probabilities": [0.8, 0.2]}"#, 1400.0);
+    }
+
+    #[test]
+    fn pareto_never_samples_below_its_scale() {
+        let distribution: Arc<dyn Distribution> = build(r#"{"distribution": "pareto", "mean1": 1000, "shape": 3}"#).unwrap();
+        let mut rng: SmallRng = SmallRng::seed_from_u64(1);
+        let min: f64 = (0..NR_SAMPLES).map(|_| distribution.sample(&mut rng)).fold(f64::MAX, f64::min);
+        let scale: f64 = 1000.0 * 2.0 / 3.0;
+        assert!((scale..scale * 1.001).contains(&min), "the smallest sample is {}, the scale is {}", min, scale);
+    }
+
+    fn samples(empirical: &Empirical) -> Vec<f64> {
+        let mut rng: SmallRng = SmallRng::seed_from_u64(1);
+        (0..NR_SAMPLES).map(|_| empirical.sample(&mut rng)).collect()
+    }
+
+    #[test]
+    fn empirical_point_mass() {
+        for empirical in [Empirical::from_samples("250\n"), Empirical::from_samples("250\n250\n250\n"), Empirical::from_cdf("250 3 0\n")] {
+            assert!(samples(&empirical.unwrap()).iter().all(|value| *value == 250.0));
+        }
+    }
+
+    #[test]
+    fn empirical_interpolates_between_points() {
+        // As written by 'script_cdf.sh' for the samples 100 and 200, and the same samples given one per line
+        for empirical in [Empirical::from_cdf("100 1 0\n200 1 1\n"), Empirical::from_samples("200\n100\n")] {
+            let samples: Vec<f64> = samples(&empirical.unwrap());
+            assert!(samples.iter().all(|value| (100.0..=200.0).contains(value)));
+            let mean: f64 = samples.iter().sum::<f64>() / NR_SAMPLES as f64;
+            assert!((mean - 150.0).abs() < 1.0, "the sample mean is {}", mean);
+            let below: usize = samples.iter().filter(|value| **value < 125.0).count();
+            assert!((below as f64 / NR_SAMPLES as f64 - 0.25).abs() < 0.01, "{} samples below the first quartile", below);
+        }
+
+        // The probabilities are normalized by the last one, and a jump between two points is not interpolated
+        let samples: Vec<f64> = samples(&Empirical::from_cdf("# value count cdf\n10 1 0\n10 1 2\n30 1 2\n30 1 4\n").unwrap());
+        let below: usize = samples.iter().filter(|value| **value == 10.0).count();
+        assert!(samples.iter().all(|value| *value == 10.0 || *value == 30.0));
+        assert!((below as f64 / NR_SAMPLES as f64 - 0.5).abs() < 0.01, "{} samples at 10", below);
+    }
+
+    #[test]
+    fn empirical_rejects_bad_files() {
+        assert_eq!(Empirical::from_cdf("100 1 0\nabc 1 1\n").unwrap_err(), "line 2: 'abc' is not a non-negative number");
+        assert_eq!(Empirical::from_cdf("200 1 0\n\n100 1 1\n").unwrap_err(), "line 3: the values and the probabilities should not decrease");
+        assert_eq!(Empirical::from_cdf("100\n").unwrap_err(), "line 1: expected the value and its cumulative probability");
+        assert_eq!(Empirical::from_cdf("100 1 0\n200 1 0\n").unwrap_err(), "the last cumulative probability should be bigger than 0");
+        assert_eq!(Empirical::from_samples("# nothing\n").unwrap_err(), "there is no sample");
+        assert_eq!(Empirical::from_samples("100\n-5\n").unwrap_err(), "line 2: '-5' is not a non-negative number");
+
+        // The errors of the file name the field holding it
+        let dir: std::path::PathBuf = std::env::temp_dir().join(format!("sim_empirical_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file: std::path::PathBuf = dir.join("bad.cdf");
+        fs::write(&file, "100 1 0\n200 1 x\n").unwrap();
+        let found: String = invalid_path(&format!(r#"{{"distribution": "empirical", "format": "cdf", "file": "{}"}}"#, file.display()));
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, "application.file");
+        assert_eq!(invalid_path(r#"{"distribution": "empirical", "file": "/nonexistent/service.dat"}"#), "application.file");
+    }
+
+    #[test]
+    fn invalid_parameters_name_their_path() {
+        assert_eq!(invalid_path(r#"{"distribution": "normal", "mean1": 1000}"#), "application.distribution");
+        assert_eq!(invalid_path(r#"{"distribution": "uniform", "min": 1500, "max": 500}"#), "application.max");
+        assert_eq!(invalid_path(r#"{"distribution": "lognormal", "mean1": 1000, "sigma": -1}"#), "application.sigma");
+        assert_eq!(invalid_path(r#"{"distribution": "lognormal", "mean1": 0, "sigma": 1}"#), "application.mean1");
+        assert_eq!(invalid_path(r#"{"distribution": "pareto", "mean1": 1000, "shape": 1}"#), "application.shape");
+        assert_eq!(invalid_path(r#"{"distribution": "weibull", "mean1": 1000, "shape": 0}"#), "application.shape");
+        assert_eq!(invalid_path(r#"{"distribution": "gamma", "mean1": -5, "shape": 2}"#), "application.mean1");
+        assert_eq!(invalid_path(r#"{"distribution": "erlang", "mean1": 1000, "shape": 0}"#), "application.shape");
+        assert_eq!(invalid_path(r#"{"distribution": "hyperexponential", "means": [500, 5000], "probabilities": [0.5, 0.4]}"#), "application.probabilities");
+        assert_eq!(invalid_path(r#"{"distribution": "hyperexponential", "means": [500, 5000], "probabilities": [1]}"#), "application.probabilities");
+        assert_eq!(invalid_path(r#"{"distribution": "hyperexponential", "means": [0, 5000], "probabilities": [0.5, 0.5]}"#), "application.means");
+
+        // A missing parameter is not invalid, it is missing
+        assert_eq!(
+            build(r#"{"distribution": "pareto", "mean1": 1000}"#).unwrap_err(),
+            ConfigError::Missing { path: "application.shape".to_string(), expected: "a number" },
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+};
+
+// Quick Explanation of this
+// The simulator only needs to look at the ticks where something can change:
+// a request arrives, a core completes a stage, a core has a request waiting in its local queue,
+// or a per-flow lock is released. Every component pushes the tick of its next interesting moment
+// here, and the simulator jumps straight to the earliest one instead of advancing one tick at a time.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventKind {
+    Arrival,
+    StageComplete(usize),
+    Enqueue(usize),
+    LockRelease(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Event {
+    time: usize,
+    kind: EventKind,
+}
+
+// Structure
+pub struct EventQueue {
+    heap: BinaryHeap<Reverse<Event>>,
+}
+
+// Associate Functions
+impl EventQueue {
+    pub fn new() -> EventQueue {
+        EventQueue {
+            heap: BinaryHeap::<Reverse<Event>>::new(),
+        }
+    }
+
+    pub fn push(&mut self, time: usize, kind: EventKind) {
+        self.heap.push(Reverse(Event { time, kind }));
+    }
+
+    // Returns the time of the earliest pending event, discarding every other event of that same time,
+    // since the simulator handles all of them in a single step.
+    pub fn pop_next(&mut self) -> Option<usize> {
+        let Reverse(event) = self.heap.pop()?;
+        log::trace!("[{:?}]: {:?}", event.time, event.kind);
+        while let Some(Reverse(next)) = self.heap.peek() {
+            if next.time > event.time {
+                break;
+            }
+            log::trace!("[{:?}]: {:?}", next.time, next.kind);
+            self.heap.pop();
+        }
+
+        Some(event.time)
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+impl Default for EventQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
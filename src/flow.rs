@@ -0,0 +1,175 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::rand::{
+    rngs::SmallRng,
+    Rng,
+    RngCore,
+};
+use rustc_serialize::json::Json;
+
+use crate::config::{
+    ConfigError,
+    get_f64,
+    get_f64_array,
+    get_opt_string,
+    get_usize,
+    at_least_one,
+};
+
+// Quick Explanation of this
+// How the requests are spread among the flows, chosen by "packets.flow_popularity":
+// - "uniform" (default): every flow is as popular;
+// - "zipf": the flow i (from 0) has weight 1/(i+1)^s, with s in "packets.zipf_exponent";
+// - "weights": explicit weights in "packets.flow_weights", one per flow;
+// - "hot": the first "packets.hot_flows" flows carry "packets.hot_share" (0 to 1) of the requests.
+// The most popular flows are always the first ones.
+
+// Structure
+#[derive(Debug, Clone)]
+pub enum FlowPopularity {
+    Uniform {
+        nr_flows: usize,
+    },
+    // Cumulative weights, normalized (the last one is 1)
+    Weighted {
+        cdf: Vec<f64>,
+    },
+    Hot {
+        nr_flows: usize,
+        hot_flows: usize,
+        hot_share: f64,
+    },
+}
+
+// Associate Functions
+impl FlowPopularity {
+    pub fn from_json(json: &Json, nr_flows: usize) -> Result<FlowPopularity, ConfigError> {
+        match get_opt_string(json, "packets.flow_popularity")?.as_deref() {
+            None | Some("uniform") => Ok(FlowPopularity::Uniform { nr_flows }),
+            Some("zipf") => {
+                let s: f64 = get_f64(json, "packets.zipf_exponent")?;
+                if s < 0.0 {
+                    return Err(ConfigError::invalid("packets.zipf_exponent", format!("should not be negative, found {}", s)));
+                }
+                let weights: Vec<f64> = (0..nr_flows).map(|i| 1.0 / ((i + 1) as f64).powf(s)).collect();
+                Ok(FlowPopularity::weighted(&weights))
+            },
+            Some("weights") => {
+                let weights: Vec<f64> = get_f64_array(json, "packets.flow_weights")?;
+                if weights.len() != nr_flows {
+                    return Err(ConfigError::invalid("packets.flow_weights", format!("should have one weight per flow ({}), found {}", nr_flows, weights.len())));
+                }
+                if weights.iter().any(|weight| *weight < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+                    return Err(ConfigError::invalid("packets.flow_weights", "should not be negative, and at least one should be bigger than 0".to_string()));
+                }
+                Ok(FlowPopularity::weighted(&weights))
+            },
+            Some("hot") => {
+                let hot_flows: usize = get_usize(json, "packets.hot_flows")?;
+                at_least_one("packets.hot_flows", hot_flows)?;
+                if hot_flows >= nr_flows {
+                    return Err(ConfigError::invalid("packets.hot_flows", format!("should be smaller than the number of flows ({}), found {}", nr_flows, hot_flows)));
+                }
+                let hot_share: f64 = get_f64(json, "packets.hot_share")?;
+                if !(0.0..=1.0).contains(&hot_share) {
+                    return Err(ConfigError::invalid("packets.hot_share", format!("should be between 0 and 1, found {}", hot_share)));
+                }
+                Ok(FlowPopularity::Hot { nr_flows, hot_flows, hot_share })
+            },
+            Some(other) => Err(ConfigError::invalid("packets.flow_popularity", format!("should be one of uniform, zipf, weights, hot, found \"{}\"", other))),
+        }
+    }
+
+    fn weighted(weights: &[f64]) -> FlowPopularity {
+        let total: f64 = weights.iter().sum();
+        let mut acc: f64 = 0.0;
+        let cdf: Vec<f64> = weights.iter().map(|weight| {
+            acc += weight / total;
+            acc
+        }).collect();
+        FlowPopularity::Weighted { cdf }
+    }
+
+    pub fn nr_flows(&self) -> usize {
+        match self {
+            FlowPopularity::Uniform { nr_flows } => *nr_flows,
+            FlowPopularity::Weighted { cdf } => cdf.len(),
+            FlowPopularity::Hot { nr_flows, .. } => *nr_flows,
+        }
+    }
+
+    pub fn sample(&self, rng: &mut SmallRng) -> usize {
+        match self {
+            FlowPopularity::Uniform { nr_flows } => (rng.next_u64() % *nr_flows as u64) as usize,
+            FlowPopularity::Weighted { cdf } => {
+                let r: f64 = rng.gen::<f64>();
+                cdf.partition_point(|p| *p <= r).min(cdf.len() - 1)
+            },
+            FlowPopularity::Hot { nr_flows, hot_flows, hot_share } => {
+                if rng.gen::<f64>() < *hot_share {
+                    rng.gen_range(0..*hot_flows)
+                } else {
+                    rng.gen_range(*hot_flows..*nr_flows)
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::rand::SeedableRng;
+
+    use super::*;
+
+    const NR_SAMPLES: usize = 200000;
+
+    // Share of the requests of each flow
+    fn shares(packets: &str, nr_flows: usize) -> Vec<f64> {
+        let json: Json = Json::from_str(&format!(r#"{{"packets": {}}}"#, packets)).unwrap();
+        let popularity: FlowPopularity = FlowPopularity::from_json(&json, nr_flows).unwrap();
+        let mut rng: SmallRng = SmallRng::seed_from_u64(1);
+        let mut counts: Vec<usize> = vec![0; popularity.nr_flows()];
+        for _ in 0..NR_SAMPLES {
+            counts[popularity.sample(&mut rng)] += 1;
+        }
+        counts.iter().map(|count| *count as f64 / NR_SAMPLES as f64).collect()
+    }
+
+    fn assert_shares(found: &[f64], expected: &[f64]) {
+        assert_eq!(found.len(), expected.len());
+        for (flow_id, (found, expected)) in found.iter().zip(expected).enumerate() {
+            assert!((found - expected).abs() < 0.005, "flow {}: share {}, expected {}", flow_id, found, expected);
+        }
+    }
+
+    #[test]
+    fn hot_flows_carry_their_share() {
+        let found: Vec<f64> = shares(r#"{"flow_popularity": "hot", "hot_flows": 2, "hot_share": 0.8}"#, 10);
+        assert_shares(&found, &[0.4, 0.4, 0.025, 0.025, 0.025, 0.025, 0.025, 0.025, 0.025, 0.025]);
+    }
+
+    #[test]
+    fn zipf_shares_follow_the_ranks() {
+        // Weights 1, 1/2, 1/3 and 1/4, normalized by their sum (25/12)
+        let found: Vec<f64> = shares(r#"{"flow_popularity": "zipf", "zipf_exponent": 1}"#, 4);
+        assert_shares(&found, &[0.48, 0.24, 0.16, 0.12]);
+
+        // Weights 1, 1/4 and 1/9
+        let found: Vec<f64> = shares(r#"{"flow_popularity": "zipf", "zipf_exponent": 2}"#, 3);
+        let total: f64 = 1.0 + 1.0 / 4.0 + 1.0 / 9.0;
+        assert_shares(&found, &[1.0 / total, 0.25 / total, 1.0 / 9.0 / total]);
+
+        // An exponent of 0 is uniform
+        let found: Vec<f64> = shares(r#"{"flow_popularity": "zipf", "zipf_exponent": 0}"#, 4);
+        assert_shares(&found, &[0.25; 4]);
+    }
+
+    #[test]
+    fn weights_are_normalized() {
+        let found: Vec<f64> = shares(r#"{"flow_popularity": "weights", "flow_weights": [1, 0, 3]}"#, 3);
+        assert_shares(&found, &[0.25, 0.0, 0.75]);
+        assert_eq!(found[1], 0.0);
+    }
+}
@@ -0,0 +1,149 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::sync::Arc;
+use ::rand::{
+    rngs::SmallRng,
+    Rng,
+};
+
+use crate::{
+    request::Request,
+    config::{
+        SimConfig,
+        ClassConfig,
+    },
+    distribution::Distribution,
+    flow::FlowPopularity,
+    trace::{
+        Trace,
+        TraceRecord,
+    },
+};
+
+// Quick Explanation of this
+// Creates the requests of a run: the class, the flow, the size in packets and the service time of each stage (from the
+// distributions of the class), drawn from their own random streams (or taken from the trace, when it has them).
+// The network stack time is per packet: one draw for each packet of the request (a recorded stack time in the trace
+// stands for the whole request), and the TX time is one draw for each packet of the response. The arrival time is given by the caller, which is either the
+// arrival process (open loop) or the clients (closed loop).
+
+// Structure
+pub struct Generator {
+    classes: Vec<ClassConfig>,
+    // Cumulative ratios of the classes, normalized (the last one is 1)
+    class_cdf: Vec<f64>,
+    flows: FlowPopularity,
+    trace: Option<Arc<Trace>>,
+    classes_rng: SmallRng,
+    packets_rng: SmallRng,
+    flows_rng: SmallRng,
+    forwarder_rng: SmallRng,
+    network_stack_rng: SmallRng,
+    application_rng: SmallRng,
+    next_id: usize,
+    // Generated requests per flow
+    flow_load: Vec<usize>,
+}
+
+// Associate Functions
+impl Generator {
+    pub fn new(config: &SimConfig, classes_rng: SmallRng, packets_rng: SmallRng, flows_rng: SmallRng, forwarder_rng: SmallRng, network_stack_rng: SmallRng, application_rng: SmallRng) -> Generator {
+        let total: f64 = config.classes.iter().map(|class| class.ratio).sum();
+        let mut acc: f64 = 0.0;
+        let class_cdf: Vec<f64> = config.classes.iter().map(|class| {
+            acc += class.ratio / total;
+            acc
+        }).collect();
+
+        Generator {
+            classes: config.classes.clone(),
+            class_cdf,
+            flows: config.packets.flows.clone(),
+            trace: config.packets.trace.clone(),
+            classes_rng,
+            packets_rng,
+            flows_rng,
+            forwarder_rng,
+            network_stack_rng,
+            application_rng,
+            next_id: 0,
+            flow_load: vec![0; config.packets.nr_flows],
+        }
+    }
+
+    // Number of requests created so far
+    pub fn get_nr_generated(&self) -> usize {
+        self.next_id
+    }
+
+    pub fn into_flow_load(self) -> Vec<usize> {
+        self.flow_load
+    }
+
+    // The next request, arriving at 't_arrival'. The flow is drawn from the popularity model unless given.
+    pub fn next_request(&mut self, t_arrival: usize, flow_id: Option<usize>) -> Request {
+        let id: usize = self.next_id;
+        self.next_id += 1;
+
+        // A single class draws nothing, so it does not change the other streams
+        let request_class: usize = match self.classes.len() {
+            1 => 0,
+            n => {
+                let r: f64 = self.classes_rng.gen::<f64>();
+                self.class_cdf.partition_point(|p| *p <= r).min(n - 1)
+            },
+        };
+        let class: &ClassConfig = &self.classes[request_class];
+
+        // A replayed request may come with its own service times
+        let record: Option<&TraceRecord> = self.trace.as_ref().map(|trace| &trace.records[id]);
+
+        // Without their distributions, a request is one packet and so is its response, if there is a TX cost
+        let nr_request_packets: usize = class.request_packets.as_ref().map_or(1, |d| (d.sample(&mut self.packets_rng).round() as usize).max(1));
+        let nr_response_packets: usize = match &class.response_packets {
+            Some(d) => (d.sample(&mut self.packets_rng).round() as usize).max(1),
+            None if class.tx_stack.is_some() => 1,
+            None => 0,
+        };
+
+        // Every stage takes at least one tick
+        let stack_time: usize = match record.and_then(|record| record.stack_time) {
+            Some(stack_time) => stack_time,
+            None => (0..nr_request_packets).map(|_| class.network_stack.sample(&mut self.network_stack_rng) as usize).sum(),
+        }.max(1);
+        // No TX stage for a response of no packets
+        let tx: &Arc<dyn Distribution> = class.tx_stack.as_ref().unwrap_or(&class.network_stack);
+        let tx_time: usize = match nr_response_packets {
+            0 => 0,
+            n => (0..n).map(|_| tx.sample(&mut self.network_stack_rng) as usize).sum::<usize>().max(1),
+        };
+        let application_time: usize = match record.and_then(|record| record.application_time) {
+            Some(application_time) => application_time,
+            None => class.application.sample(&mut self.application_rng) as usize,
+        }.max(1);
+        let forwarder_time: usize = match record.and_then(|record| record.forwarder_time) {
+            Some(forwarder_time) => forwarder_time,
+            None => class.forwarder.sample(&mut self.forwarder_rng) as usize,
+        }.max(1);
+        let flow_id: usize = match (record, flow_id) {
+            (Some(record), _) => record.flow_id,
+            (None, Some(flow_id)) => flow_id,
+            (None, None) => self.flows.sample(&mut self.flows_rng),
+        };
+        self.flow_load[flow_id] += 1;
+
+        let mut req: Request = Request::new(
+            id,
+            flow_id,
+            request_class,
+            t_arrival,
+            forwarder_time,
+            stack_time,
+            application_time,
+        );
+        req.set_nr_packets(nr_request_packets, nr_response_packets);
+        req.set_tx_time(tx_time);
+        req
+    }
+}
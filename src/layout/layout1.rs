@@ -0,0 +1,122 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use crate::{
+    Request,
+    CoreState,
+    CoreAction,
+    config::{
+        SimConfig,
+        ConfigError,
+        at_least_one,
+    },
+    worker_core::{
+        Core,
+        CoreStats,
+    },
+    results::DropStage,
+    dispatcher::{
+        Dispatcher,
+        WorkerView,
+    },
+};
+use super::{
+    Layout,
+    Context,
+    section,
+    check_nr_cores,
+};
+
+// Quick Explanation of this
+// Layout 1: a forwarder core receives all packets and forwards each one to a worker core chosen by the dispatcher
+// (an idle one by default), see 'Dispatcher'. The workers run both the network stack and the application,
+// holding a spinlock of the flow meanwhile.
+
+// Structure
+pub struct Layout1 {
+    forwarder: Core,
+    worker_cores: Vec<Core>,
+    locks: Vec<usize>,
+    dispatcher: Dispatcher,
+}
+
+impl Layout for Layout1 {
+    fn build(config: &SimConfig) -> Result<Layout1, ConfigError> {
+        let queue_size: usize = config.queue_size;
+        let nr_flows: usize = config.packets.nr_flows;
+        let nr_worker_cores: usize = section(&config.layout1, "layout1")?.nr_worker_cores;
+        at_least_one("layout1.nr_worker_cores", nr_worker_cores)?;
+        check_nr_cores("layout1.nr_worker_cores", nr_worker_cores + 1, config.nr_total_cores)?;
+        if config.stealing.is_some() {
+            return Err(ConfigError::invalid("stealing", "is not supported in Layout 1 (its workers may be spinning on a flow lock)".to_string()));
+        }
+
+        let forwarder: Core = Core::new(0, CoreAction::Forward, queue_size, config.network_discipline.clone());
+
+        let mut worker_cores: Vec<Core> = Vec::<Core>::with_capacity(nr_worker_cores);
+        for i in 0..nr_worker_cores {
+            let core: Core = Core::new(i + 1, CoreAction::NetworkStackAndApplicationLock, queue_size, config.discipline.clone());
+            worker_cores.push(core);
+        }
+
+        // Locks for each flow
+        let locks: Vec<usize> = vec![usize::MAX; nr_flows];
+
+        Ok(Layout1 {
+            forwarder,
+            worker_cores,
+            locks,
+            dispatcher: Dispatcher::new(config.dispatcher, DropStage::Dispatcher),
+        })
+    }
+
+    fn id(&self) -> usize {
+        1
+    }
+
+    fn route(&mut self, req: Request, ctx: &mut Context) {
+        if let Err(req) = self.forwarder.try_enqueue(req, ctx.t_cur, ctx.events) {
+            ctx.drop(&mut self.forwarder, req, DropStage::Forwarder);
+        }
+    }
+
+    fn step(&mut self, ctx: &mut Context) {
+        // First, we make progress on all worker cores, starting from the last core that received a new request.
+        let views: Vec<WorkerView> = Dispatcher::snapshot(&self.worker_cores);
+        let n: usize = self.worker_cores.len();
+        let last_worker_idx: usize = self.dispatcher.get_last_worker_idx();
+        for idx in (last_worker_idx..n).chain(0..last_worker_idx) {
+            let core: &mut Core = &mut self.worker_cores[idx];
+            if let CoreState::Finished(req) = core.schedule(ctx.t_cur, Some(&mut self.locks), ctx.events) {
+                ctx.finish(core, req);
+            }
+        }
+
+        // Second, the forwarded packet waits in the dispatcher buffer (unless the forwarder is holding another one)
+        if !self.dispatcher.is_blocked(&self.forwarder) {
+            if let CoreState::Finished(req) = self.forwarder.schedule(ctx.t_cur, None, ctx.events) {
+                log::warn!("[{:?}]: Forwarder Core #{:?} finished the Request #{:?}", ctx.t_cur, self.forwarder.get_id(), req.get_id());
+                if let Err(req) = self.forwarder.try_enqueue_ready_queue(req) {
+                    ctx.drop(&mut self.forwarder, req, DropStage::Dispatcher);
+                }
+            }
+        }
+
+        // Third, we forward the buffered packets to the workers
+        self.dispatcher.dispatch(&mut self.forwarder, &mut self.worker_cores, views, ctx);
+    }
+
+    fn core_stats(&self) -> Vec<CoreStats> {
+        let mut stats: Vec<CoreStats> = vec![self.forwarder.get_stats()];
+        stats.extend(self.worker_cores.iter().map(|core| core.get_stats()));
+        stats
+    }
+
+    fn cores(&self) -> Vec<&Core> {
+        std::iter::once(&self.forwarder).chain(self.worker_cores.iter()).collect()
+    }
+
+    fn locks(&self) -> Option<&[usize]> {
+        Some(&self.locks)
+    }
+}
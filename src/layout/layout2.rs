@@ -0,0 +1,99 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use crate::{
+    Request,
+    CoreState,
+    CoreAction,
+    config::{
+        SimConfig,
+        ConfigError,
+        at_least_one,
+    },
+    nic::Nic,
+    worker_core::{
+        Core,
+        CoreStats,
+    },
+    results::DropStage,
+    stealing::{
+        self,
+        StealingConfig,
+    },
+};
+use super::{
+    Layout,
+    Context,
+    section,
+    check_nr_cores,
+};
+
+// Quick Explanation of this
+// Layout 2: each worker core owns one RX queue of the NIC (RSS) and runs both the network stack and the application.
+// With work stealing, an idle worker core may take the requests waiting in the queue of another one.
+
+// Structure
+pub struct Layout2 {
+    nic: Nic,
+    worker_cores: Vec<Core>,
+    stealing: Option<StealingConfig>,
+}
+
+impl Layout for Layout2 {
+    fn build(config: &SimConfig) -> Result<Layout2, ConfigError> {
+        let queue_size: usize = config.queue_size;
+        let nr_indirection_table_entries: usize = config.nic.nr_indirection_table_entries;
+        let nr_worker_cores: usize = section(&config.layout2, "layout2")?.nr_worker_cores;
+        at_least_one("layout2.nr_worker_cores", nr_worker_cores)?;
+        check_nr_cores("layout2.nr_worker_cores", nr_worker_cores, config.nr_total_cores)?;
+
+        let mut worker_cores: Vec<Core> = Vec::<Core>::with_capacity(nr_worker_cores);
+        for i in 0..nr_worker_cores {
+            let core: Core = Core::new(i, CoreAction::NetworkStackAndApplication, queue_size, config.discipline.clone());
+            worker_cores.push(core);
+        }
+
+        Ok(Layout2 {
+            nic: Nic::new(nr_indirection_table_entries, nr_worker_cores),
+            worker_cores,
+            stealing: config.stealing.clone(),
+        })
+    }
+
+    fn id(&self) -> usize {
+        2
+    }
+
+    fn route(&mut self, req: Request, ctx: &mut Context) {
+        let core: &mut Core = &mut self.worker_cores[self.nic.get_queue(req.get_flow_id())];
+        if let Err(req) = core.try_enqueue(req, ctx.t_cur, ctx.events) {
+            ctx.drop(core, req, DropStage::NetworkStack);
+        }
+    }
+
+    fn queue_of(&self, flow_id: usize) -> usize {
+        self.nic.get_queue(flow_id)
+    }
+
+    fn step(&mut self, ctx: &mut Context) {
+        // We just make progress in all worker cores
+        for core in self.worker_cores.iter_mut() {
+            if let CoreState::Finished(req) = core.schedule(ctx.t_cur, None, ctx.events) {
+                ctx.finish(core, req);
+            }
+        }
+
+        // The idle cores steal the requests waiting behind busy ones
+        if let Some(config) = &self.stealing {
+            stealing::steal(config, std::slice::from_mut(&mut self.worker_cores), ctx);
+        }
+    }
+
+    fn core_stats(&self) -> Vec<CoreStats> {
+        self.worker_cores.iter().map(|core| core.get_stats()).collect()
+    }
+
+    fn cores(&self) -> Vec<&Core> {
+        self.worker_cores.iter().collect()
+    }
+}
@@ -0,0 +1,143 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use crate::{
+    Request,
+    CoreState,
+    CoreAction,
+    config::{
+        SimConfig,
+        ConfigError,
+        at_least_one,
+    },
+    worker_core::{
+        Core,
+        CoreStats,
+    },
+    results::DropStage,
+    dispatcher::{
+        Dispatcher,
+        DispatchPolicy,
+        WorkerView,
+    },
+    stealing::{
+        self,
+        StealingConfig,
+    },
+};
+use super::{
+    Layout,
+    Context,
+    section,
+    check_nr_cores,
+};
+
+// Quick Explanation of this
+// Layout 3: a single network core receives all packets and runs the network stack,
+// then hands each request to an application core chosen by the dispatcher (an idle one by default), see 'Dispatcher'. The responses with a TX stage go back to the network core.
+// With work stealing, an idle application core may take the requests waiting in the queue of another one.
+
+// Structure
+pub struct Layout3 {
+    network_core: Core,
+    application_cores: Vec<Core>,
+    dispatcher: Dispatcher,
+    stealing: Option<StealingConfig>,
+}
+
+impl Layout for Layout3 {
+    fn build(config: &SimConfig) -> Result<Layout3, ConfigError> {
+        let queue_size: usize = config.queue_size;
+        let nr_application_cores: usize = section(&config.layout3, "layout3")?.nr_application_cores;
+        at_least_one("layout3.nr_application_cores", nr_application_cores)?;
+        check_nr_cores("layout3.nr_application_cores", nr_application_cores + 1, config.nr_total_cores)?;
+        if config.stealing.is_some() && config.dispatcher.policy == DispatchPolicy::FirstIdle {
+            return Err(ConfigError::invalid("stealing", "needs a dispatcher that hands the requests to busy cores in Layout 3 (\"first_idle\" only hands them to idle ones, leaving nothing to steal)".to_string()));
+        }
+
+        let network_core: Core = Core::new(0, CoreAction::NetworkStack, queue_size, config.network_discipline.clone());
+
+        let mut application_cores: Vec<Core> = Vec::<Core>::with_capacity(nr_application_cores);
+        for i in 0..nr_application_cores {
+            let core: Core = Core::new(i + 1, CoreAction::Application, queue_size, config.discipline.clone());
+            application_cores.push(core);
+        }
+
+        Ok(Layout3 {
+            network_core,
+            application_cores,
+            dispatcher: Dispatcher::new(config.dispatcher, DropStage::Application),
+            stealing: config.stealing.clone(),
+        })
+    }
+
+    fn id(&self) -> usize {
+        3
+    }
+
+    fn route(&mut self, req: Request, ctx: &mut Context) {
+        if let Err(req) = self.network_core.try_enqueue(req, ctx.t_cur, ctx.events) {
+            ctx.drop(&mut self.network_core, req, DropStage::NetworkStack);
+        }
+    }
+
+    fn step(&mut self, ctx: &mut Context) {
+        // First, we make progress on all application cores, starting from the last core that received a new request.
+        let views: Vec<WorkerView> = Dispatcher::snapshot(&self.application_cores);
+        let n: usize = self.application_cores.len();
+        let last_worker_idx: usize = self.dispatcher.get_last_worker_idx();
+        let mut responses: Vec<Request> = Vec::<Request>::new();
+        for idx in (last_worker_idx..n).chain(0..last_worker_idx) {
+            let core: &mut Core = &mut self.application_cores[idx];
+            if let CoreState::Finished(req) = core.schedule(ctx.t_cur, None, ctx.events) {
+                if req.get_tx_time() > 0 {
+                    responses.push(req);
+                } else {
+                    ctx.finish(core, req);
+                }
+            }
+        }
+
+        // Second, we make progress in the network core (enqueuing to ready_queue, when the processing completes),
+        // unless it is holding a request that no application core took yet
+        let state: CoreState = match self.dispatcher.is_blocked(&self.network_core) {
+            true => CoreState::Running,
+            false => self.network_core.schedule(ctx.t_cur, None, ctx.events),
+        };
+        match state {
+            CoreState::Finished(req) if req.is_t_completed() => {
+                // The response was sent
+                ctx.finish(&mut self.network_core, req);
+            },
+            CoreState::Finished(req) => {
+                if let Err(req) = self.network_core.try_enqueue_ready_queue(req) {
+                    ctx.drop(&mut self.network_core, req, self.dispatcher.get_drop_stage());
+                }
+            },
+            _ => {},
+        }
+
+        // Third, we hand the requests of the ready_queue to the application cores
+        self.dispatcher.dispatch(&mut self.network_core, &mut self.application_cores, views, ctx);
+
+        // The network core sends the new responses from the next round
+        for req in responses {
+            self.network_core.enqueue_tx(req, ctx.t_cur, ctx.events);
+        }
+
+        // The idle application cores steal the requests waiting behind busy ones
+        if let Some(config) = &self.stealing {
+            stealing::steal(config, std::slice::from_mut(&mut self.application_cores), ctx);
+        }
+    }
+
+    fn core_stats(&self) -> Vec<CoreStats> {
+        let mut stats: Vec<CoreStats> = vec![self.network_core.get_stats()];
+        stats.extend(self.application_cores.iter().map(|core| core.get_stats()));
+        stats
+    }
+
+    fn cores(&self) -> Vec<&Core> {
+        std::iter::once(&self.network_core).chain(self.application_cores.iter()).collect()
+    }
+}
@@ -0,0 +1,179 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use crate::{
+    Request,
+    CoreState,
+    CoreAction,
+    config::{
+        SimConfig,
+        ConfigError,
+        Layout4Config,
+        at_least_one,
+    },
+    nic::Nic,
+    worker_core::{
+        Core,
+        CoreStats,
+    },
+    results::DropStage,
+    stealing::{
+        self,
+        StealingConfig,
+    },
+};
+use super::{
+    Layout,
+    Context,
+    section,
+    check_nr_cores,
+};
+
+// Quick Explanation of this
+// Layout 4: several network cores, each one owning one RX queue of the NIC (RSS).
+// Each network core runs the network stack and hands the requests to an idle application core of its own group.
+// The responses with a TX stage go back to the network core that owns their flow.
+// With work stealing, the network cores also hand the requests to busy application cores of their group when none
+// is idle, and an idle application core may take the requests waiting in the queue of another one.
+
+// Structure
+pub struct Layout4 {
+    nic: Nic,
+    network_cores: Vec<Core>,
+    // The application cores of each network core (indexed by the network core id)
+    application_cores: Vec<Vec<Core>>,
+    last_workers_idx: Vec<usize>,
+    stealing: Option<StealingConfig>,
+}
+
+impl Layout for Layout4 {
+    fn build(config: &SimConfig) -> Result<Layout4, ConfigError> {
+        let queue_size: usize = config.queue_size;
+        let nr_indirection_table_entries: usize = config.nic.nr_indirection_table_entries;
+        let layout4: &Layout4Config = section(&config.layout4, "layout4")?;
+        let nr_network_cores: usize = layout4.nr_network_cores;
+        let nr_application_cores: usize = layout4.nr_application_cores;
+
+        at_least_one("layout4.nr_network_cores", nr_network_cores)?;
+        check_nr_cores("layout4", nr_network_cores + nr_application_cores, config.nr_total_cores)?;
+
+        if nr_application_cores < nr_network_cores {
+            return Err(ConfigError::invalid("layout4.nr_application_cores", format!("number of application cores ({}) should be bigger than the number of network stack cores ({})", nr_application_cores, nr_network_cores)));
+        }
+
+        let mut network_cores: Vec<Core> = Vec::<Core>::with_capacity(nr_network_cores);
+        let mut application_cores: Vec<Vec<Core>> = Vec::<Vec<Core>>::with_capacity(nr_network_cores);
+        for core_id in 0..nr_network_cores {
+            let core: Core = Core::new(core_id, CoreAction::NetworkStack, queue_size, config.network_discipline.clone());
+            network_cores.push(core);
+            application_cores.push(Vec::new());
+        }
+
+        for i in 0..nr_application_cores {
+            let core_id: usize = nr_network_cores + i;
+            let core: Core = Core::new(core_id, CoreAction::Application, queue_size, config.discipline.clone());
+            let network_id: usize = core_id % nr_network_cores;
+            application_cores[network_id].push(core);
+        }
+
+        Ok(Layout4 {
+            nic: Nic::new(nr_indirection_table_entries, nr_network_cores),
+            network_cores,
+            application_cores,
+            last_workers_idx: vec![0; nr_network_cores],
+            stealing: config.stealing.clone(),
+        })
+    }
+
+    fn id(&self) -> usize {
+        4
+    }
+
+    fn route(&mut self, req: Request, ctx: &mut Context) {
+        let core: &mut Core = &mut self.network_cores[self.nic.get_queue(req.get_flow_id())];
+        if let Err(req) = core.try_enqueue(req, ctx.t_cur, ctx.events) {
+            ctx.drop(core, req, DropStage::NetworkStack);
+        }
+    }
+
+    fn queue_of(&self, flow_id: usize) -> usize {
+        self.nic.get_queue(flow_id)
+    }
+
+    fn step(&mut self, ctx: &mut Context) {
+        // First, we need make progress in application cores
+        let mut responses: Vec<Request> = Vec::<Request>::new();
+        for core in self.application_cores.iter_mut().flatten() {
+            if let CoreState::Finished(req) = core.schedule(ctx.t_cur, None, ctx.events) {
+                if req.get_tx_time() > 0 {
+                    responses.push(req);
+                } else {
+                    ctx.finish(core, req);
+                }
+            }
+        }
+
+        // Second, for EACH network stack core, we see if there is an idle application core
+        for (network_id, network_core) in self.network_cores.iter_mut().enumerate() {
+            // We make progress in the network core (enqueuing to ready_queue, when the processing completes)
+            match network_core.schedule(ctx.t_cur, None, ctx.events) {
+                CoreState::Finished(req) if req.is_t_completed() => {
+                    // The response was sent
+                    ctx.finish(network_core, req);
+                },
+                CoreState::Finished(req) => {
+                    if let Err(req) = network_core.try_enqueue_ready_queue(req) {
+                        ctx.drop(network_core, req, DropStage::Application);
+                    }
+                },
+                _ => {},
+            }
+
+            // The requests of the ready_queue go to the idle application cores of the group, in order
+            let group: &mut Vec<Core> = &mut self.application_cores[network_id];
+            let n: usize = group.len();
+            let mut taken: Vec<bool> = vec![false; n];
+            while !network_core.is_ready_queue_empty() {
+                let last_worker_idx: usize = self.last_workers_idx[network_id];
+                let idle_worker_core: Option<usize> = (0..n)
+                    .map(|i| (last_worker_idx + i + 1) % n)
+                    .find(|idx| !taken[*idx] && group[*idx].is_idle());
+                let idx: usize = match idle_worker_core {
+                    Some(idx) => idx,
+                    // With work stealing, the request waits behind the least loaded core of the group (where an idle
+                    // core of any group may take it) instead of in the ready_queue
+                    None if self.stealing.is_some() => match (0..n).filter(|idx| !group[*idx].is_full()).min_by_key(|idx| group[*idx].get_load()) {
+                        Some(idx) => idx,
+                        None => break,
+                    },
+                    None => break,
+                };
+                self.last_workers_idx[network_id] = idx;
+                taken[idx] = true;
+                let req: Request = network_core.pop_ready_queue();
+                group[idx].try_enqueue(req, ctx.t_cur, ctx.events).expect("ERROR: should not be here.")
+            }
+        }
+
+        // The network core of each flow sends its new responses from the next round
+        for req in responses {
+            let network_core: &mut Core = &mut self.network_cores[self.nic.get_queue(req.get_flow_id())];
+            network_core.enqueue_tx(req, ctx.t_cur, ctx.events);
+        }
+
+        // The idle application cores steal the requests waiting behind busy ones (of any group, unless 'same_group')
+        if let Some(config) = &self.stealing {
+            stealing::steal(config, &mut self.application_cores, ctx);
+        }
+    }
+
+    fn core_stats(&self) -> Vec<CoreStats> {
+        let mut stats: Vec<CoreStats> = self.network_cores.iter().map(|core| core.get_stats()).collect();
+        stats.extend(self.application_cores.iter().flatten().map(|core| core.get_stats()));
+        stats
+    }
+
+    fn cores(&self) -> Vec<&Core> {
+        self.network_cores.iter().chain(self.application_cores.iter().flatten()).collect()
+    }
+}
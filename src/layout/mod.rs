@@ -0,0 +1,153 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::rand::rngs::SmallRng;
+
+use crate::{
+    Request,
+    config::{
+        SimConfig,
+        ConfigError,
+    },
+    event::EventQueue,
+    results::{
+        DropRecord,
+        DropStage,
+        LatencyRecord,
+    },
+    audit::Auditor,
+    worker_core::{
+        Core,
+        CoreStats,
+    },
+};
+
+mod layout1;
+mod layout2;
+mod layout3;
+mod layout4;
+
+pub use layout1::Layout1;
+pub use layout2::Layout2;
+pub use layout3::Layout3;
+pub use layout4::Layout4;
+
+// Quick Explanation of this
+// A layout is a server architecture: how the cores are organized, which core receives each incoming request,
+// and how the requests move between the cores. The simulator only talks to the layouts through the 'Layout' trait,
+// so a new architecture only needs its own module and an entry in 'LAYOUTS'.
+
+// Everything a layout may touch while making progress at 't_cur'
+pub struct Context<'a> {
+    pub t_cur: usize,
+    pub events: &'a mut EventQueue,
+    pub finished: &'a mut Vec<LatencyRecord>,
+    pub dropped: &'a mut Vec<DropRecord>,
+    // Random decisions of the layout
+    pub rng: &'a mut SmallRng,
+    // Checks the requests leaving the server, in audit mode
+    pub auditor: Option<&'a mut Auditor>,
+}
+
+impl Context<'_> {
+    pub fn finish(&mut self, core: &mut Core, req: Request) {
+        log::warn!("[{:?}]: Core #{:?} finished the Request #{:?}", self.t_cur, core.get_id(), req.get_id());
+        core.add_finished();
+        if let Some(auditor) = &mut self.auditor {
+            auditor.check_request(&req);
+        }
+        self.finished.push(LatencyRecord {
+            id: req.get_id(),
+            flow_id: req.get_flow_id(),
+            request_class: req.get_request_class(),
+            core_id: core.get_id(),
+            t_arrival: req.get_arrival_time(),
+            t_departure: req.get_departure_time(),
+        });
+    }
+
+    pub fn drop(&mut self, core: &mut Core, mut req: Request, stage: DropStage) {
+        match stage {
+            DropStage::Forwarder => req.set_f_dropped(),
+            DropStage::NetworkStack => req.set_p_dropped(),
+            DropStage::Application | DropStage::Dispatcher => req.set_r_dropped(),
+        }
+        core.add_dropped();
+        if let Some(auditor) = &mut self.auditor {
+            auditor.check_request(&req);
+        }
+        self.dropped.push(DropRecord {
+            id: req.get_id(),
+            flow_id: req.get_flow_id(),
+            request_class: req.get_request_class(),
+            core_id: core.get_id(),
+            t_arrival: req.get_arrival_time(),
+            t_dropped: self.t_cur,
+            stage,
+        });
+    }
+}
+
+// Layouts are Send so each simulation can run on its own thread
+pub trait Layout: Send {
+    // Builds the layout from the configuration (checking its own section)
+    fn build(config: &SimConfig) -> Result<Self, ConfigError> where Self: Sized;
+
+    // The number used to identify this layout in the configuration and in the output files
+    fn id(&self) -> usize;
+
+    // Delivers a request that just arrived to the core that receives it (or drops it)
+    fn route(&mut self, req: Request, ctx: &mut Context);
+
+    // The RX queue where the requests of 'flow_id' arrive (used to report the load of each queue)
+    fn queue_of(&self, _flow_id: usize) -> usize {
+        0
+    }
+
+    // Makes progress in all cores at 'ctx.t_cur'
+    fn step(&mut self, ctx: &mut Context);
+
+    // Counters of each core of this layout
+    fn core_stats(&self) -> Vec<CoreStats>;
+
+    // Every core of this layout (used by the audit mode)
+    fn cores(&self) -> Vec<&Core>;
+
+    // The owner core of each flow lock (usize::MAX when free), for the layouts with flow locks
+    fn locks(&self) -> Option<&[usize]> {
+        None
+    }
+}
+
+type LayoutBuilder = fn(&SimConfig) -> Result<Box<dyn Layout>, ConfigError>;
+
+// All known layouts, identified by the 'layout' field of the configuration
+const LAYOUTS: &[(usize, LayoutBuilder)] = &[
+    (1, |config| Ok(Box::new(Layout1::build(config)?))),
+    (2, |config| Ok(Box::new(Layout2::build(config)?))),
+    (3, |config| Ok(Box::new(Layout3::build(config)?))),
+    (4, |config| Ok(Box::new(Layout4::build(config)?))),
+];
+
+pub fn build(which_layout: usize, config: &SimConfig) -> Result<Box<dyn Layout>, ConfigError> {
+    match LAYOUTS.iter().find(|(id, _)| *id == which_layout) {
+        Some((_, build)) => build(config),
+        None => {
+            let ids: Vec<String> = LAYOUTS.iter().map(|(id, _)| id.to_string()).collect();
+            Err(ConfigError::invalid("layout", format!("should be one of {}, found {}", ids.join(", "), which_layout)))
+        },
+    }
+}
+
+// The section of the configuration of a layout (e.g. "layout1")
+fn section<'a, T>(section: &'a Option<T>, path: &'static str) -> Result<&'a T, ConfigError> {
+    section.as_ref().ok_or(ConfigError::Missing { path: path.to_string(), expected: "an object with the configuration of the selected layout" })
+}
+
+// Checks that the cores used by a layout fit in the server
+fn check_nr_cores(path: &str, nr_cores: usize, nr_total_cores: usize) -> Result<(), ConfigError> {
+    if nr_cores > nr_total_cores {
+        return Err(ConfigError::invalid(path, format!("the layout needs {} cores, but nr_total_cores is {}", nr_cores, nr_total_cores)));
+    }
+    Ok(())
+}
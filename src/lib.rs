@@ -0,0 +1,52 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Quick Explanation of this
+// The simulator as a library: build a 'SimConfig' (e.g. from the JSON configuration), create a 'Simulation'
+// for each run and 'run' it to get the 'SimulationResults'. The 'sim' binary is a thin wrapper over it.
+
+pub mod request;
+use request::Request;
+
+pub mod worker_core;
+pub mod discipline;
+pub mod stealing;
+pub mod dispatcher;
+pub mod event;
+pub mod nic;
+pub mod config;
+pub mod distribution;
+pub mod trace;
+pub mod arrival;
+pub mod flow;
+pub mod generator;
+pub mod rng;
+pub mod stats;
+pub mod sweep;
+pub mod pool;
+pub mod layout;
+pub mod simulation;
+pub mod results;
+pub mod audit;
+pub mod output;
+
+pub use config::{
+    ConfigError,
+    SimConfig,
+};
+pub use simulation::Simulation;
+pub use results::SimulationResults;
+pub use audit::AuditError;
+
+pub enum CoreAction {
+    Forward,
+    Application,
+    NetworkStack,
+    NetworkStackAndApplication,
+    NetworkStackAndApplicationLock,
+}
+
+pub enum CoreState {
+    Idle,
+    Running,
+    Finished(Request),
+}
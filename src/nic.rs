@@ -0,0 +1,29 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Quick Explanation of this
+// The NIC spreads the flows among its RX queues through RSS: the flow is hashed into an entry
+// of the indirection table, and each entry points to a queue (Round-Robin for the queue_id <-> entry relationship).
+
+// Structure
+pub struct Nic {
+    indirection_table: Vec<usize>,
+}
+
+// Associate Functions
+impl Nic {
+    pub fn new(nr_indirection_table_entries: usize, nr_queues: usize) -> Nic {
+        let mut indirection_table: Vec<usize> = Vec::<usize>::with_capacity(nr_indirection_table_entries);
+        for i in 0..nr_indirection_table_entries {
+            indirection_table.push(i % nr_queues);
+        }
+
+        Nic {
+            indirection_table,
+        }
+    }
+
+    pub fn get_queue(&self, flow_id: usize) -> usize {
+        let entry: usize = flow_id % self.indirection_table.len();
+        self.indirection_table[entry]
+    }
+}
@@ -0,0 +1,214 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fs::File,
+    io::{
+        BufWriter,
+        Write,
+    },
+    path::Path,
+    collections::BTreeMap,
+};
+use rustc_serialize::json::{
+    Json,
+    ToJson,
+};
+
+use crate::{
+    results::{
+        DropRecord,
+        LatencyRecord,
+        SimulationResults,
+    },
+    stats::percentiles,
+};
+
+// Quick Explanation of this
+// Writers of the results of one run. Each one takes a 'SimulationResults' and writes one file in 'out_dir',
+// named after the layout and the run (e.g. layout1_run0.csv).
+
+fn filename(results: &SimulationResults, extension: &str) -> String {
+    format!("layout{:?}_run{:?}.{}", results.layout_id, results.run_id, extension)
+}
+
+// Latencies (RTT) of the finished requests, one per line, in the order they finished
+pub fn write_raw(results: &SimulationResults, out_dir: &Path) -> std::io::Result<()> {
+    let mut file: BufWriter<File> = BufWriter::new(File::create(out_dir.join(filename(results, "dat")))?);
+
+    for record in &results.finished {
+        writeln!(file, "{}", results.latency(record))?;
+    }
+    file.flush()
+}
+
+// One row: #total requests, #received, #completed, #dropped, min, p25, p50, p75, p99.9, p99.99, max, finished/dropped per core...
+pub fn write_stats(results: &SimulationResults, out_dir: &Path) -> csv::Result<()> {
+    let mut writer: csv::Writer<File> = csv::Writer::from_path(out_dir.join(filename(results, "csv")))?;
+
+    let arr: Vec<usize> = results.latencies();
+
+    let mut row: Vec<usize> = vec![
+        results.nr_packets,
+        results.received,
+        results.finished.len(),
+        results.dropped.len(),
+        percentiles(&arr, 0.0),
+        percentiles(&arr, 25.0),
+        percentiles(&arr, 50.0),
+        percentiles(&arr, 75.0),
+        percentiles(&arr, 99.9),
+        percentiles(&arr, 99.99),
+        percentiles(&arr, 100.0),
+    ];
+
+    for stats in &results.core_stats {
+        row.push(stats.finished);
+        row.push(stats.dropped);
+    }
+
+    writer.serialize(row)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Time series of the run, one row per window (see 'SimulationResults::windows')
+pub fn write_windows(results: &SimulationResults, out_dir: &Path) -> csv::Result<()> {
+    let filename: String = format!("layout{:?}_run{:?}_windows.csv", results.layout_id, results.run_id);
+    let mut writer: csv::Writer<File> = csv::Writer::from_path(out_dir.join(filename))?;
+
+    writer.write_record(["t_start", "t_end", "received", "finished", "dropped", "throughput", "p50", "p99", "p99.9"])?;
+    for w in results.windows() {
+        writer.write_record([
+            w.t_start.to_string(),
+            w.t_end.to_string(),
+            w.received.to_string(),
+            w.finished.to_string(),
+            w.dropped.to_string(),
+            w.throughput.to_string(),
+            w.p50.to_string(),
+            w.p99.to_string(),
+            w.p999.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Generated requests per flow and per RX queue, with their share of all requests
+pub fn write_load(results: &SimulationResults, out_dir: &Path) -> csv::Result<()> {
+    let total: f64 = results.flow_load.iter().sum::<usize>().max(1) as f64;
+
+    for (name, load) in [("flow", &results.flow_load), ("queue", &results.queue_load)] {
+        let filename: String = format!("layout{:?}_run{:?}_{}s.csv", results.layout_id, results.run_id, name);
+        let mut writer: csv::Writer<File> = csv::Writer::from_path(out_dir.join(filename))?;
+
+        writer.write_record([name, "requests", "share"])?;
+        for (id, requests) in load.iter().enumerate() {
+            writer.write_record([id.to_string(), requests.to_string(), (*requests as f64 / total).to_string()])?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+// Latencies and SLO violations of each class (see 'SimulationResults::class_stats')
+pub fn write_classes(results: &SimulationResults, out_dir: &Path) -> csv::Result<()> {
+    let filename: String = format!("layout{:?}_run{:?}_classes.csv", results.layout_id, results.run_id);
+    let mut writer: csv::Writer<File> = csv::Writer::from_path(out_dir.join(filename))?;
+
+    writer.write_record(["class", "name", "finished", "dropped", "p50", "p99", "p99.9", "slo", "slo_violations", "slo_violation_rate"])?;
+    for (id, c) in results.class_stats().into_iter().enumerate() {
+        writer.write_record([
+            id.to_string(),
+            c.name.clone(),
+            c.finished.to_string(),
+            c.dropped.to_string(),
+            c.p50.to_string(),
+            c.p99.to_string(),
+            c.p999.to_string(),
+            c.slo.map_or(String::new(), |slo| slo.to_string()),
+            c.slo.map_or(String::new(), |_| c.slo_violations.to_string()),
+            c.slo_violation_rate().map_or(String::new(), |rate| rate.to_string()),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Everything in the results, including every finished and dropped request
+pub fn write_json(results: &SimulationResults, out_dir: &Path) -> std::io::Result<()> {
+    let mut file: BufWriter<File> = BufWriter::new(File::create(out_dir.join(filename(results, "json")))?);
+
+    writeln!(file, "{}", results.to_json())?;
+    file.flush()
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect::<BTreeMap<String, Json>>())
+}
+
+fn latency_record_to_json(results: &SimulationResults, record: &LatencyRecord) -> Json {
+    object(vec![
+        ("id", record.id.to_json()),
+        ("flow_id", record.flow_id.to_json()),
+        ("class", record.request_class.to_json()),
+        ("core_id", record.core_id.to_json()),
+        ("t_arrival", record.t_arrival.to_json()),
+        ("t_departure", record.t_departure.to_json()),
+        ("latency", results.latency(record).to_json()),
+    ])
+}
+
+impl ToJson for DropRecord {
+    fn to_json(&self) -> Json {
+        object(vec![
+            ("id", self.id.to_json()),
+            ("flow_id", self.flow_id.to_json()),
+            ("class", self.request_class.to_json()),
+            ("core_id", self.core_id.to_json()),
+            ("t_arrival", self.t_arrival.to_json()),
+            ("t_dropped", self.t_dropped.to_json()),
+            ("stage", self.stage.name().to_json()),
+        ])
+    }
+}
+
+impl ToJson for SimulationResults {
+    fn to_json(&self) -> Json {
+        let core_stats: Vec<Json> = self.core_stats.iter().map(|stats| object(vec![
+            ("core_id", stats.core_id.to_json()),
+            ("finished", stats.finished.to_json()),
+            ("dropped", stats.dropped.to_json()),
+            ("preemptions", stats.preemptions.to_json()),
+            ("steals", stats.steals.to_json()),
+        ])).collect();
+        let classes: Vec<Json> = self.class_stats().iter().map(|c| object(vec![
+            ("name", c.name.to_json()),
+            ("slo", c.slo.to_json()),
+            ("finished", c.finished.to_json()),
+            ("dropped", c.dropped.to_json()),
+            ("p50", c.p50.to_json()),
+            ("p99", c.p99.to_json()),
+            ("p99.9", c.p999.to_json()),
+            ("slo_violations", c.slo_violations.to_json()),
+        ])).collect();
+
+        object(vec![
+            ("layout", self.layout_id.to_json()),
+            ("run", self.run_id.to_json()),
+            ("nr_packets", self.nr_packets.to_json()),
+            ("received", self.received.to_json()),
+            ("rtt_base", self.rtt_base.to_json()),
+            ("t_start", self.t_start.to_json()),
+            ("t_end", self.t_end.to_json()),
+            ("duration", self.duration().to_json()),
+            ("core_stats", Json::Array(core_stats)),
+            ("flow_load", self.flow_load.to_json()),
+            ("queue_load", self.queue_load.to_json()),
+            ("classes", Json::Array(classes)),
+            ("finished", Json::Array(self.finished.iter().map(|record| latency_record_to_json(self, record)).collect())),
+            ("dropped", self.dropped.to_json()),
+        ])
+    }
+}
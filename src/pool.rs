@@ -0,0 +1,47 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Mutex,
+    },
+    thread,
+};
+
+// Quick Explanation of this
+// Runs independent jobs (e.g. the runs of every sweep point) on 'nr_threads' threads.
+// Each thread takes the next job index until there are no more jobs. Results are returned by job index,
+// so they do not depend on the number of threads nor on the order the jobs finish.
+
+pub fn run_jobs<T, F>(nr_jobs: usize, nr_threads: usize, job: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    let next_job: AtomicUsize = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new((0..nr_jobs).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..nr_threads.clamp(1, nr_jobs.max(1)) {
+            scope.spawn(|| loop {
+                let i: usize = next_job.fetch_add(1, Ordering::Relaxed);
+                if i >= nr_jobs {
+                    break;
+                }
+                let result: T = job(i);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results.into_inner().unwrap().into_iter().map(|result| result.expect("every job runs once")).collect()
+}
+
+// Default number of threads: one per available CPU
+pub fn default_nr_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
@@ -25,23 +25,38 @@ pub struct Request {
     // Request
     is_r_dropped: bool,
     is_r_completed: bool,
+    // Response
+    is_t_completed: bool,
     // Packet infomation
     flow_id: usize,
+    // Index of the class in the configuration
+    request_class: usize,
+    // Size of the request and of its response
+    nr_request_packets: usize,
+    nr_response_packets: usize,
     // Forward information
     t_f_start: usize,
     t_f_end: usize,
-    f_completed: usize,
     forward_time: usize,
     // Packet information
     t_p_start: usize,
     t_p_end: usize,
-    p_completed: usize,
     stack_time: usize,
     // Request information
     t_r_start: usize,
     t_r_end: usize,
-    r_completed: usize,
     request_time: usize,
+    // Response information (no TX stage when 0)
+    t_t_start: usize,
+    t_t_end: usize,
+    tx_time: usize,
+    // Ticks still to run of each stage, less than its time once the stage is preempted
+    forward_left: usize,
+    stack_left: usize,
+    request_left: usize,
+    tx_left: usize,
+    // Preempted in the middle of a stage, waiting to resume it
+    is_preempted: bool,
 }
 
 // Associate Functions
@@ -49,12 +64,13 @@ impl Request {
     pub fn new(
         id: usize,
         flow_id: usize,
+        request_class: usize,
         t_arrival: usize,
         forward_time: usize,
         stack_time: usize,
         request_time: usize,
     ) -> Request {
-        let req = Request {
+        Request {
             id,
             t_arrival,
             t_departure: 0,
@@ -64,22 +80,29 @@ impl Request {
             is_p_completed: false,
             is_r_dropped: false,
             is_r_completed: false,
+            is_t_completed: false,
             flow_id,
+            request_class,
+            nr_request_packets: 1,
+            nr_response_packets: 0,
             t_f_start: 0,
             t_f_end: 0,
-            f_completed: 0,
             forward_time,
             t_p_start: 0,
             t_p_end: 0,
-            p_completed: 0,
             stack_time,
             t_r_start: 0,
             t_r_end: 0,
-            r_completed: 0,
             request_time,
-        };
-
-        req
+            t_t_start: 0,
+            t_t_end: 0,
+            tx_time: 0,
+            forward_left: forward_time,
+            stack_left: stack_time,
+            request_left: request_time,
+            tx_left: 0,
+            is_preempted: false,
+        }
     }
 
     pub fn set_id(&mut self, id: usize) {
@@ -98,6 +121,23 @@ impl Request {
         self.flow_id
     }
 
+    pub fn get_request_class(&self) -> usize {
+        self.request_class
+    }
+
+    pub fn set_nr_packets(&mut self, nr_request_packets: usize, nr_response_packets: usize) {
+        self.nr_request_packets = nr_request_packets;
+        self.nr_response_packets = nr_response_packets;
+    }
+
+    pub fn get_nr_request_packets(&self) -> usize {
+        self.nr_request_packets
+    }
+
+    pub fn get_nr_response_packets(&self) -> usize {
+        self.nr_response_packets
+    }
+
     pub fn get_start(&self) -> usize {
         self.t_p_start
     }
@@ -118,6 +158,10 @@ impl Request {
         self.t_departure
     }
 
+    pub fn get_forward_time(&self) -> usize {
+        self.forward_time
+    }
+
     pub fn get_stack_time(&self) -> usize {
         self.stack_time
     }
@@ -126,6 +170,39 @@ impl Request {
         self.request_time
     }
 
+    pub fn set_tx_time(&mut self, tx_time: usize) {
+        self.tx_time = tx_time;
+        self.tx_left = tx_time;
+    }
+
+    pub fn get_tx_time(&self) -> usize {
+        self.tx_time
+    }
+
+    pub fn get_forward_left(&self) -> usize {
+        self.forward_left
+    }
+
+    pub fn get_stack_left(&self) -> usize {
+        self.stack_left
+    }
+
+    pub fn get_request_left(&self) -> usize {
+        self.request_left
+    }
+
+    pub fn get_tx_left(&self) -> usize {
+        self.tx_left
+    }
+
+    pub fn is_preempted(&self) -> bool {
+        self.is_preempted
+    }
+
+    pub fn set_preempted(&mut self) {
+        self.is_preempted = true;
+    }
+
     pub fn is_f_completed(&self) -> bool {
         self.is_f_completed
     }
@@ -138,28 +215,28 @@ impl Request {
         self.is_r_completed
     }
 
+    pub fn is_t_completed(&self) -> bool {
+        self.is_t_completed
+    }
+
     pub fn set_f_start(&mut self, t_cur: usize) {
         self.t_f_start = t_cur;
+        self.is_preempted = false;
     }
 
     pub fn set_p_start(&mut self, t_cur: usize) {
         self.t_p_start = t_cur;
+        self.is_preempted = false;
     }
 
     pub fn set_r_start(&mut self, t_cur: usize) {
         self.t_r_start = t_cur;
+        self.is_preempted = false;
     }
 
-    pub fn set_f_end(&mut self, t_cur: usize) {
-        self.t_f_end = t_cur;
-    }
-
-    pub fn set_p_end(&mut self, t_cur: usize) {
-        self.t_p_end = t_cur;
-    }
-
-    pub fn set_r_end(&mut self, t_cur: usize) {
-        self.t_r_end = t_cur;
+    pub fn set_t_start(&mut self, t_cur: usize) {
+        self.t_t_start = t_cur;
+        self.is_preempted = false;
     }
 
     pub fn set_f_dropped(&mut self) {
@@ -174,32 +251,96 @@ impl Request {
         self.is_r_dropped = true;
     }
 
-    pub fn f_schedule(&mut self) -> bool {
-        self.f_completed += 1;
-        if self.f_completed == self.forward_time {
+    // Name, whether it completed, start and end of each stage, in the order they run
+    pub fn get_stages(&self) -> [(&'static str, bool, usize, usize); 4] {
+        [
+            ("forwarder", self.is_f_completed, self.t_f_start, self.t_f_end),
+            ("network stack", self.is_p_completed, self.t_p_start, self.t_p_end),
+            ("application", self.is_r_completed, self.t_r_start, self.t_r_end),
+            ("TX", self.is_t_completed, self.t_t_start, self.t_t_end),
+        ]
+    }
+
+    // A stage that started at 't_start' and lasts 'duration' ticks is completed at 't_start + duration - 1'
+    // (the starting tick already counts as one tick of processing).
+    pub fn get_f_deadline(&self) -> usize {
+        self.t_f_start + self.forward_left - 1
+    }
+
+    pub fn get_p_deadline(&self) -> usize {
+        self.t_p_start + self.stack_left - 1
+    }
+
+    pub fn get_r_deadline(&self) -> usize {
+        self.t_r_start + self.request_left - 1
+    }
+
+    pub fn get_t_deadline(&self) -> usize {
+        self.t_t_start + self.tx_left - 1
+    }
+
+    // Moves the start of the stage running on a core (the forwarder, or else the first stage not completed) to 't_cur',
+    // leaving in the ticks left of the stage only those still to run, so it can be preempted and resumed later
+    // (the deadline does not change, and the start of a resumed stage is when it resumed). Returns false, changing
+    // nothing, when the stage completes by 't_cur' (so a resumed stage always has more than one tick left).
+    pub fn checkpoint(&mut self, t_cur: usize, forwarding: bool) -> bool {
+        let (t_start, left): (&mut usize, &mut usize) = if forwarding {
+            (&mut self.t_f_start, &mut self.forward_left)
+        } else if !self.is_p_completed {
+            (&mut self.t_p_start, &mut self.stack_left)
+        } else if !self.is_r_completed {
+            (&mut self.t_r_start, &mut self.request_left)
+        } else {
+            (&mut self.t_t_start, &mut self.tx_left)
+        };
+
+        if *t_start + *left <= t_cur + 1 {
+            return false;
+        }
+        if t_cur > *t_start {
+            *left -= t_cur - *t_start;
+            *t_start = t_cur;
+        }
+        true
+    }
+
+    // Each stage completes (and ends) at the first tick from its deadline where it is scheduled: a stage of one tick
+    // ends when it starts, even if the core only notices it in the next round.
+    pub fn f_schedule(&mut self, t_cur: usize) -> bool {
+        if !self.is_f_completed && t_cur >= self.get_f_deadline() {
             self.is_f_completed = true;
+            self.t_f_end = t_cur;
         }
 
         self.is_f_completed
     }
 
-    pub fn p_schedule(&mut self) -> bool {
-        self.p_completed += 1;
-        if self.p_completed == self.stack_time {
+    pub fn p_schedule(&mut self, t_cur: usize) -> bool {
+        if !self.is_p_completed && t_cur >= self.get_p_deadline() {
             self.is_p_completed = true;
+            self.t_p_end = t_cur;
         }
 
         self.is_p_completed
     }
 
-    pub fn r_schedule(&mut self) -> bool {
-        self.r_completed += 1;
-        if self.r_completed == self.request_time {
+    pub fn r_schedule(&mut self, t_cur: usize) -> bool {
+        if !self.is_r_completed && t_cur >= self.get_r_deadline() {
             self.is_r_completed = true;
+            self.t_r_end = t_cur;
         }
 
         self.is_r_completed
     }
+
+    pub fn t_schedule(&mut self, t_cur: usize) -> bool {
+        if !self.is_t_completed && t_cur >= self.get_t_deadline() {
+            self.is_t_completed = true;
+            self.t_t_end = t_cur;
+        }
+
+        self.is_t_completed
+    }
 }
 
 impl fmt::Debug for Request {
@@ -0,0 +1,237 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use crate::{
+    worker_core::CoreStats,
+    stats::{
+        RunSummary,
+        percentiles,
+    },
+};
+
+// Quick Explanation of this
+// Everything a simulation gives back after running. Nothing here is written to files:
+// the writers in 'output' (or any other code) take a 'SimulationResults' and post-process it.
+
+// Where the request was when it was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropStage {
+    // Waiting for the forwarder (Layout 1)
+    Forwarder,
+    // Waiting for the network stack
+    NetworkStack,
+    // Processed by the network stack, waiting for an application core
+    Application,
+    // Forwarded, waiting for a worker (Layout 1)
+    Dispatcher,
+}
+
+impl DropStage {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DropStage::Forwarder => "forwarder",
+            DropStage::NetworkStack => "network_stack",
+            DropStage::Application => "application",
+            DropStage::Dispatcher => "dispatcher",
+        }
+    }
+}
+
+// One finished request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyRecord {
+    pub id: usize,
+    pub flow_id: usize,
+    pub request_class: usize,
+    // Core that finished the request
+    pub core_id: usize,
+    pub t_arrival: usize,
+    pub t_departure: usize,
+}
+
+// One dropped request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DropRecord {
+    pub id: usize,
+    pub flow_id: usize,
+    pub request_class: usize,
+    // Core that dropped the request
+    pub core_id: usize,
+    pub t_arrival: usize,
+    pub t_dropped: usize,
+    pub stage: DropStage,
+}
+
+// What happened during one window of time: requests are counted in the window where they arrived,
+// finished or were dropped, and the latencies are of the requests finished in the window
+#[derive(Debug, Clone, Copy)]
+pub struct WindowStats {
+    pub t_start: usize,
+    pub t_end: usize,
+    pub received: usize,
+    pub finished: usize,
+    pub dropped: usize,
+    // Finished requests per second
+    pub throughput: f64,
+    pub p50: usize,
+    pub p99: usize,
+    pub p999: usize,
+}
+
+// A class of requests, as configured
+#[derive(Debug, Clone)]
+pub struct RequestClass {
+    pub name: String,
+    // Latency (RTT) target in ticks, if any
+    pub slo: Option<usize>,
+}
+
+// What happened to the requests of one class
+#[derive(Debug, Clone)]
+pub struct ClassStats {
+    pub name: String,
+    pub slo: Option<usize>,
+    pub finished: usize,
+    pub dropped: usize,
+    pub p50: usize,
+    pub p99: usize,
+    pub p999: usize,
+    // Requests that missed the SLO: finished above it, or dropped (0 without SLO)
+    pub slo_violations: usize,
+}
+
+impl ClassStats {
+    // Violations over the finished and dropped requests of the class, if it has an SLO
+    pub fn slo_violation_rate(&self) -> Option<f64> {
+        self.slo.map(|_| self.slo_violations as f64 / (self.finished + self.dropped).max(1) as f64)
+    }
+}
+
+// Structure
+pub struct SimulationResults {
+    pub layout_id: usize,
+    pub run_id: usize,
+    pub nr_packets: usize,
+    pub received: usize,
+    // Width of the windows (in ticks) and the arrivals in each one, see 'windows'
+    pub window: Option<usize>,
+    pub received_per_window: Vec<usize>,
+    pub rtt_base: usize,
+    // Simulated time, from the first arrival to the end of the simulation (in ticks)
+    pub t_start: usize,
+    pub t_end: usize,
+    // In the order the requests finished/were dropped
+    pub finished: Vec<LatencyRecord>,
+    pub dropped: Vec<DropRecord>,
+    // Indexed by core id, including the cores not used by the layout
+    pub core_stats: Vec<CoreStats>,
+    // Generated requests per flow and per RX queue of the layout
+    pub flow_load: Vec<usize>,
+    pub queue_load: Vec<usize>,
+    // Indexed by the class of the requests, see 'class_stats'
+    pub classes: Vec<RequestClass>,
+}
+
+// Associate Functions
+impl SimulationResults {
+    pub fn duration(&self) -> usize {
+        self.t_end - self.t_start
+    }
+
+    // Latency (RTT) of a finished request
+    pub fn latency(&self, record: &LatencyRecord) -> usize {
+        record.t_departure - record.t_arrival + self.rtt_base
+    }
+
+    // Sorted latencies (RTT) of the finished requests
+    pub fn latencies(&self) -> Vec<usize> {
+        let mut arr: Vec<usize> = self.finished.iter().map(|record| self.latency(record)).collect();
+        arr.sort();
+        arr
+    }
+
+    pub fn nr_dropped_at(&self, stage: DropStage) -> usize {
+        self.dropped.iter().filter(|record| record.stage == stage).count()
+    }
+
+    // Time series of the run, from the window of the first arrival to the end (empty without 'window')
+    pub fn windows(&self) -> Vec<WindowStats> {
+        let window: usize = match self.window {
+            None => return Vec::<WindowStats>::new(),
+            Some(window) => window,
+        };
+
+        let first: usize = self.t_start / window;
+        // A request finished at the last tick departs in the next one
+        let nr_windows: usize = (self.t_end + 1) / window + 1 - first;
+        let mut latencies: Vec<Vec<usize>> = vec![Vec::<usize>::new(); nr_windows];
+        let mut dropped: Vec<usize> = vec![0; nr_windows];
+        for record in &self.finished {
+            latencies[record.t_departure / window - first].push(self.latency(record));
+        }
+        for record in &self.dropped {
+            dropped[record.t_dropped / window - first] += 1;
+        }
+
+        latencies.into_iter().enumerate().map(|(i, mut arr)| {
+            arr.sort();
+            WindowStats {
+                t_start: (first + i) * window,
+                t_end: (first + i + 1) * window,
+                received: self.received_per_window.get(first + i).cloned().unwrap_or(0),
+                finished: arr.len(),
+                dropped: dropped[i],
+                throughput: arr.len() as f64 / (window as f64 / 1_000_000_000.0),
+                p50: percentiles(&arr, 50.0),
+                p99: percentiles(&arr, 99.0),
+                p999: percentiles(&arr, 99.9),
+            }
+        }).collect()
+    }
+
+    // Latencies and SLO violations of each class
+    pub fn class_stats(&self) -> Vec<ClassStats> {
+        let mut latencies: Vec<Vec<usize>> = vec![Vec::<usize>::new(); self.classes.len()];
+        let mut dropped: Vec<usize> = vec![0; self.classes.len()];
+        for record in &self.finished {
+            latencies[record.request_class].push(self.latency(record));
+        }
+        for record in &self.dropped {
+            dropped[record.request_class] += 1;
+        }
+
+        self.classes.iter().zip(latencies).zip(dropped).map(|((class, mut arr), dropped)| {
+            arr.sort();
+            let late: usize = match class.slo {
+                None => 0,
+                Some(slo) => arr.len() - arr.partition_point(|latency| *latency <= slo),
+            };
+            ClassStats {
+                name: class.name.clone(),
+                slo: class.slo,
+                finished: arr.len(),
+                dropped,
+                p50: percentiles(&arr, 50.0),
+                p99: percentiles(&arr, 99.0),
+                p999: percentiles(&arr, 99.9),
+                slo_violations: if class.slo.is_some() { late + dropped } else { 0 },
+            }
+        }).collect()
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        let arr: Vec<usize> = self.latencies();
+        let t_elapsed: f64 = self.duration().max(1) as f64 / 1_000_000_000.0;
+
+        RunSummary {
+            p50: percentiles(&arr, 50.0),
+            p99: percentiles(&arr, 99.0),
+            p999: percentiles(&arr, 99.9),
+            p9999: percentiles(&arr, 99.99),
+            throughput: self.finished.len() as f64 / t_elapsed,
+            dropped: self.dropped.len(),
+            drop_rate: self.dropped.len() as f64 / self.received.max(1) as f64,
+            classes: self.class_stats(),
+        }
+    }
+}
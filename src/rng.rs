@@ -0,0 +1,67 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::rand::{
+    rngs::SmallRng,
+    SeedableRng,
+};
+
+// Quick Explanation of this
+// Each run has its own random streams, derived only from the configured seed and the run id,
+// so any run can be reproduced alone. Each source of randomness (arrivals, flows, request classes, message sizes,
+// the service time of each stage and the layout) draws from its own stream, so changing one distribution does not shift the others.
+
+#[derive(Debug, Clone, Copy)]
+pub enum Stream {
+    Arrivals,
+    Flows,
+    Forwarder,
+    NetworkStack,
+    Application,
+    Classes,
+    Packets,
+    Layout,
+}
+
+// Structure
+pub struct RngStreams {
+    pub arrivals: SmallRng,
+    pub flows: SmallRng,
+    pub forwarder: SmallRng,
+    pub network_stack: SmallRng,
+    pub application: SmallRng,
+    pub classes: SmallRng,
+    pub packets: SmallRng,
+    // Random decisions of the layout (e.g. the victims of work stealing)
+    pub layout: SmallRng,
+}
+
+// Associate Functions
+impl RngStreams {
+    pub fn new(seed: u64, run_id: usize) -> RngStreams {
+        let stream = |stream: Stream| SmallRng::seed_from_u64(derive_seed(seed, run_id, stream));
+
+        RngStreams {
+            arrivals: stream(Stream::Arrivals),
+            flows: stream(Stream::Flows),
+            forwarder: stream(Stream::Forwarder),
+            network_stack: stream(Stream::NetworkStack),
+            application: stream(Stream::Application),
+            classes: stream(Stream::Classes),
+            packets: stream(Stream::Packets),
+            layout: stream(Stream::Layout),
+        }
+    }
+}
+
+// SplitMix64 finalizer: close inputs (e.g. consecutive run ids) give unrelated outputs
+fn mix(x: u64) -> u64 {
+    let mut z: u64 = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub fn derive_seed(seed: u64, run_id: usize, stream: Stream) -> u64 {
+    mix(mix(mix(seed) ^ run_id as u64) ^ stream as u64)
+}
@@ -0,0 +1,406 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fmt::Write,
+    collections::{
+        HashMap,
+        VecDeque,
+    },
+    sync::Arc,
+};
+use ::rand::rngs::SmallRng;
+use indicatif::{
+    ProgressBar, 
+    ProgressState, 
+    ProgressStyle
+};
+use log::info;
+
+use crate::{
+    request::Request,
+    worker_core::CoreStats,
+    event::{
+        EventKind,
+        EventQueue,
+    },
+    config::{
+        Engine,
+        SimConfig,
+        ConfigError,
+    },
+    rng::RngStreams,
+    audit::{
+        Auditor,
+        AuditError,
+    },
+    arrival::Arrivals,
+    distribution::Distribution,
+    generator::Generator,
+    results::{
+        DropRecord,
+        LatencyRecord,
+        RequestClass,
+        SimulationResults,
+    },
+    layout::{
+        self,
+        Layout,
+        Context,
+    },
+};
+
+// Quick Explanation of this
+// A simulation of one run, created from the configuration: 'run' consumes it, giving back the finished/dropped
+// requests and the counters of each core. The requests are generated lazily, as the time advances: in open loop
+// the next request is generated when the previous one arrives, and with closed-loop clients when a connection
+// gets the answer of its last one. So only the requests in the server are kept as 'Request' values. The memory
+// still grows with the number of requests: the results keep one record per finished or dropped request, the audit
+// keeps the id of every outcome, and a trace is loaded in full before the run.
+// In audit mode, the invariants of the server are checked after the steps, see 'Auditor'.
+
+// Closed-loop clients (see 'ClientsConfig')
+struct Clients {
+    think_time: Arc<dyn Distribution>,
+    rng: SmallRng,
+    nr_flows: usize,
+    // Connection that sent each request in the server, by request id
+    connection_of: HashMap<usize, usize>,
+    // Finished/dropped requests already answered to their connections
+    nr_finished_seen: usize,
+    nr_dropped_seen: usize,
+}
+
+// Structure
+pub struct Simulation {
+    // Server Related
+    nr_total_cores: usize,
+    layout: Box<dyn Layout>,
+
+    // Simulator Related
+    run_id: usize,
+    engine: Engine,
+    events: EventQueue,
+    t_start: usize,
+    t_cur: usize,
+    t_duration: usize,
+    nr_packets: usize,
+    received: usize,
+    // Arrivals per window of 'window' ticks, when the time series is enabled
+    window: Option<usize>,
+    received_per_window: Vec<usize>,
+    rtt_base: usize,
+    dropped: Vec<DropRecord>,
+    finished: Vec<LatencyRecord>,
+    // Generated requests that did not arrive yet, by arrival time
+    packets: VecDeque<Request>,
+    arrivals: Option<Arrivals>,
+    generator: Generator,
+    layout_rng: SmallRng,
+    clients: Option<Clients>,
+    classes: Vec<RequestClass>,
+    // Only in audit mode, checking every 'audit_every' steps
+    auditor: Option<Auditor>,
+    audit_every: usize,
+    nr_steps: usize,
+    progress_bar: ProgressBar,
+    show_progress: bool,
+}
+
+fn new_progress_bar(len: usize, show_progress: bool) -> ProgressBar {
+    if show_progress {
+        ProgressBar::new(len as u64)
+    } else {
+        ProgressBar::hidden()
+    }
+}
+
+// Associate Functions
+impl Simulation {
+    // 'show_progress' draws the progress bars, it should be false when several simulations run at the same time
+    pub fn new(run_id: usize, config: &SimConfig, show_progress: bool) -> Result<Simulation, ConfigError> {
+        info!("Creating the Simulation");
+
+        let t_duration: usize = config.duration;
+        let nr_total_cores: usize = config.nr_total_cores;
+
+        let layout: Box<dyn Layout> = layout::build(config.layout, config)?;
+
+        let nr_packets: usize = config.packets.nr_packets;
+        let RngStreams { arrivals: arrivals_rng, flows, forwarder, network_stack, application, classes, packets: packets_rng, layout: layout_rng } = RngStreams::new(config.seed, run_id);
+        let mut generator: Generator = Generator::new(config, classes, packets_rng, flows, forwarder, network_stack, application);
+
+        // Open loop: the requests are generated one at a time, when the previous one arrives
+        let mut packets: VecDeque<Request> = VecDeque::<Request>::new();
+        let (arrivals, clients): (Option<Arrivals>, Option<Clients>) = match &config.clients {
+            None => {
+                let arrivals: Arrivals = config.packets.arrivals.clone().expect("open loop has an arrival process").arrivals(arrivals_rng);
+                (Some(arrivals), None)
+            },
+            Some(clients_config) => {
+                // Each connection sends its first request after thinking, always on the same flow
+                let mut clients: Clients = Clients {
+                    think_time: clients_config.think_time.clone(),
+                    rng: arrivals_rng,
+                    nr_flows: config.packets.nr_flows,
+                    connection_of: HashMap::<usize, usize>::new(),
+                    nr_finished_seen: 0,
+                    nr_dropped_seen: 0,
+                };
+                for connection in 0..clients_config.connections.min(nr_packets) {
+                    let t_arrival: usize = clients.think_time.sample(&mut clients.rng) as usize;
+                    let req: Request = generator.next_request(t_arrival, Some(connection % clients.nr_flows));
+                    clients.connection_of.insert(req.get_id(), connection);
+                    packets.push_back(req);
+                }
+                packets.make_contiguous().sort_by_key(|req| req.get_arrival_time());
+
+                (None, Some(clients))
+            },
+        };
+
+        let progress_bar: ProgressBar = new_progress_bar(t_duration, show_progress);
+        progress_bar.set_style(ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{bar:40.blue/white}] {pos:>7}/{len:7} (ticks) [{percent}]")
+            .unwrap()
+            .with_key("percent", |state: &ProgressState, w: &mut dyn Write| write!(w, "{:.2}%", (state.fraction() * 100.0)).unwrap())
+            .progress_chars("#>-"));
+
+        let rtt_base: usize = config.rtt_base;
+
+        let mut sim: Simulation = Simulation {
+            // Server Related
+            nr_total_cores,
+            layout,
+
+            // Simulator Related
+            run_id,
+            engine: config.engine,
+            events: EventQueue::new(),
+            t_start: 0,
+            t_cur: 0,
+            t_duration,
+            nr_packets,
+            received: 0,
+            window: config.window,
+            received_per_window: Vec::<usize>::new(),
+            rtt_base,
+            dropped: Vec::<DropRecord>::new(),
+            finished: Vec::<LatencyRecord>::new(),
+            packets,
+            arrivals,
+            generator,
+            layout_rng,
+            clients,
+            classes: config.classes.iter().map(|class| RequestClass { name: class.name.clone(), slo: class.slo }).collect(),
+            auditor: config.audit.map(|_| Auditor::new()),
+            audit_every: config.audit.unwrap_or(1),
+            nr_steps: 0,
+            progress_bar,
+            show_progress,
+        };
+        sim.generate_next();
+        Ok(sim)
+    }
+
+    // Open loop: generates the next request once every generated one has arrived.
+    // When the arrivals end, the run only has the requests generated so far.
+    fn generate_next(&mut self) {
+        if let Some(arrivals) = &mut self.arrivals {
+            if self.packets.is_empty() && self.generator.get_nr_generated() < self.nr_packets {
+                match arrivals.next() {
+                    Some(t_arrival) => self.packets.push_back(self.generator.next_request(t_arrival, None)),
+                    None => self.nr_packets = self.generator.get_nr_generated(),
+                }
+            }
+        }
+    }
+
+    fn has_remaining_requests(&self) -> bool {
+        self.dropped.len() + self.finished.len() < self.nr_packets
+    }
+
+    // One step of the simulator at 't_cur': cores make progress, then the requests arriving now are enqueued.
+    fn step(&mut self, received_requests: &mut Vec<Request>) {
+        // Schedule all cores to make progress.
+        self.layout.step(&mut Context {
+            t_cur: self.t_cur,
+            events: &mut self.events,
+            finished: &mut self.finished,
+            dropped: &mut self.dropped,
+            rng: &mut self.layout_rng,
+            auditor: self.auditor.as_mut(),
+        });
+
+        // Check for new incoming requests.
+        if !self.packets.is_empty() {
+            let next_arrival_time: usize = self.packets[0].get_arrival_time();
+            if self.t_cur == next_arrival_time {
+                // Check for requests that arrived in the same time.
+                while !self.packets.is_empty() {
+                    if next_arrival_time == self.packets[0].get_arrival_time() {
+                        let req: Request = self.packets.pop_front().unwrap();
+                        received_requests.push(req);
+                        self.generate_next();
+                        self.received += 1;
+                        if let Some(window) = self.window {
+                            let idx: usize = self.t_cur / window;
+                            if self.received_per_window.len() <= idx {
+                                self.received_per_window.resize(idx + 1, 0);
+                            }
+                            self.received_per_window[idx] += 1;
+                        }
+                    } else {
+                        break;
+                    }
+                }
+
+                // Wake up again for the next arrival.
+                if let Some(req) = self.packets.front() {
+                    self.events.push(req.get_arrival_time(), EventKind::Arrival);
+                }
+            }
+        }
+
+        // Enqueue the incoming requests received at time 't_cur' to the cores
+        let mut ctx: Context = Context {
+            t_cur: self.t_cur,
+            events: &mut self.events,
+            finished: &mut self.finished,
+            dropped: &mut self.dropped,
+            rng: &mut self.layout_rng,
+            auditor: self.auditor.as_mut(),
+        };
+        while let Some(req) = received_requests.pop() {
+            self.layout.route(req, &mut ctx);
+        }
+
+        // Closed loop: the connections answered now send their next request
+        if let Some(clients) = &mut self.clients {
+            let answered: Vec<(usize, usize)> = self.finished[clients.nr_finished_seen..].iter().map(|record| (record.id, record.t_departure))
+                .chain(self.dropped[clients.nr_dropped_seen..].iter().map(|record| (record.id, record.t_dropped)))
+                .collect();
+            clients.nr_finished_seen = self.finished.len();
+            clients.nr_dropped_seen = self.dropped.len();
+
+            for (id, t_answered) in answered {
+                if self.generator.get_nr_generated() == self.nr_packets {
+                    break;
+                }
+                let connection: usize = clients.connection_of.remove(&id).expect("every request has a connection");
+                // The response travels back to the client, which thinks before sending again
+                let t_think: usize = clients.think_time.sample(&mut clients.rng) as usize;
+                let t_arrival: usize = (t_answered + self.rtt_base + t_think).max(self.t_cur + 1);
+                let req: Request = self.generator.next_request(t_arrival, Some(connection % clients.nr_flows));
+                clients.connection_of.insert(req.get_id(), connection);
+
+                let idx: usize = self.packets.partition_point(|other| other.get_arrival_time() <= t_arrival);
+                self.packets.insert(idx, req);
+                self.events.push(t_arrival, EventKind::Arrival);
+            }
+        }
+    }
+
+    // In audit mode, checks the requests that left the server after every step, and the whole server every
+    // 'audit_every' steps
+    fn audit(&mut self) -> Result<(), AuditError> {
+        let auditor: &mut Auditor = match &mut self.auditor {
+            None => return Ok(()),
+            Some(auditor) => auditor,
+        };
+        self.nr_steps += 1;
+        let result: Result<(), String> = match auditor.take_violation() {
+            Some(violation) => Err(violation),
+            None if self.nr_steps.is_multiple_of(self.audit_every) => auditor.check(self.generator.get_nr_generated(), &self.packets, &*self.layout, &self.finished, &self.dropped),
+            None => Ok(()),
+        };
+        result.map_err(|reason| AuditError { run_id: self.run_id, t_cur: self.t_cur, reason })
+    }
+
+    // Runs till the duration or till every request is finished or dropped.
+    // Fails in audit mode when an invariant breaks.
+    pub fn run(mut self) -> Result<SimulationResults, AuditError> {
+        if self.show_progress {
+            println!("\nRunning the simulator...");
+        }
+
+        // Set the current time of the simulator as the arrival time of the first request.
+        self.t_cur = self.packets.front().map_or(0, |req| req.get_arrival_time());
+        self.t_start = self.t_cur;
+        self.progress_bar.inc(self.t_cur as u64);
+        self.events.push(self.t_cur, EventKind::Arrival);
+
+        // Array for the incoming requests.
+        let mut received_requests: Vec<Request> = Vec::<Request>::new();
+
+        match self.engine {
+            Engine::Event => {
+                // We jump from event to event till the duration and have remaining requests to be processed.
+                while let Some(t_next) = self.events.pop_next() {
+                    if t_next >= self.t_duration || !self.has_remaining_requests() {
+                        break;
+                    }
+
+                    // Move ticks forward.
+                    self.progress_bar.inc((t_next - self.t_cur) as u64);
+                    self.t_cur = t_next;
+
+                    self.step(&mut received_requests);
+                    self.audit()?;
+                }
+            },
+            Engine::Tick => {
+                // We run till the duration and have remaining requests to be processed.
+                while self.t_cur < self.t_duration && self.has_remaining_requests() {
+                    self.step(&mut received_requests);
+                    self.audit()?;
+                    self.events.clear();
+
+                    // Move ticks forward.
+                    self.t_cur += 1;
+                    self.progress_bar.inc(1);
+                }
+            },
+        }
+
+        self.progress_bar.finish();
+        if self.show_progress {
+            println!("Done.");
+        }
+
+        // Finished/dropped per core, including the cores not used by the layout
+        let mut core_stats: Vec<CoreStats> = (0..self.nr_total_cores).map(|core_id| CoreStats { core_id, ..CoreStats::default() }).collect();
+        for stats in self.layout.core_stats() {
+            core_stats[stats.core_id] = stats;
+        }
+
+        // Generated requests per RX queue
+        let mut queue_load: Vec<usize> = Vec::<usize>::new();
+        let flow_load: Vec<usize> = self.generator.into_flow_load();
+        for (flow_id, load) in flow_load.iter().enumerate() {
+            let queue: usize = self.layout.queue_of(flow_id);
+            if queue_load.len() <= queue {
+                queue_load.resize(queue + 1, 0);
+            }
+            queue_load[queue] += load;
+        }
+
+        Ok(SimulationResults {
+            layout_id: self.layout.id(),
+            run_id: self.run_id,
+            nr_packets: self.nr_packets,
+            received: self.received,
+            window: self.window,
+            received_per_window: self.received_per_window,
+            rtt_base: self.rtt_base,
+            t_start: self.t_start,
+            t_end: self.t_cur,
+            finished: self.finished,
+            dropped: self.dropped,
+            core_stats,
+            flow_load,
+            queue_load,
+            classes: self.classes,
+        })
+    }
+
+}
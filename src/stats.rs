@@ -0,0 +1,106 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use crate::results::ClassStats;
+
+// Quick Explanation of this
+// Statistics over the latencies of one run, and over the results of several replications of the same
+// configuration (mean and 95% confidence interval with the Student's t-distribution).
+
+// Two-sided 95% critical values of the Student's t-distribution, for 1 to 30 degrees of freedom
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+fn t_95(degrees_of_freedom: usize) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::NAN,
+        1..=30 => T_95[degrees_of_freedom - 1],
+        31..=40 => 2.021,
+        41..=60 => 2.000,
+        61..=120 => 1.980,
+        _ => 1.960,
+    }
+}
+
+// 'arr' must be sorted
+pub fn percentiles(arr: &[usize], p: f64) -> usize {
+    if arr.is_empty() {
+        return 0;
+    }
+
+    let mut idx: usize = (arr.len() as f64 * (p/100.0)) as usize;
+    if idx == arr.len() {
+        idx -= 1;
+    }
+    arr[idx]
+}
+
+// Structure
+#[derive(Debug, Clone, Copy)]
+pub struct MeanCi {
+    pub mean: f64,
+    // Half width of the 95% confidence interval (None with less than two samples)
+    pub half_width: Option<f64>,
+}
+
+// Associate Functions
+impl MeanCi {
+    pub fn new(samples: &[f64]) -> MeanCi {
+        let n: usize = samples.len();
+        let mean: f64 = samples.iter().sum::<f64>() / n as f64;
+        if n < 2 {
+            return MeanCi { mean, half_width: None };
+        }
+
+        let variance: f64 = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        let half_width: f64 = t_95(n - 1) * (variance / n as f64).sqrt();
+
+        MeanCi { mean, half_width: Some(half_width) }
+    }
+}
+
+// The metrics of one run that are aggregated across the replications
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub p50: usize,
+    pub p99: usize,
+    pub p999: usize,
+    pub p9999: usize,
+    // Completed requests per second
+    pub throughput: f64,
+    pub dropped: usize,
+    // Dropped requests over received requests
+    pub drop_rate: f64,
+    pub classes: Vec<ClassStats>,
+}
+
+// Mean and 95% confidence interval of each metric, as (name, value) rows. With several classes, their latencies
+// are also reported (e.g. "get.p99"), and so is the SLO violation rate of each class with an SLO.
+pub fn aggregate(runs: &[RunSummary]) -> Vec<(String, MeanCi)> {
+    let metric = |f: &dyn Fn(&RunSummary) -> f64| MeanCi::new(&runs.iter().map(f).collect::<Vec<f64>>());
+
+    let mut rows: Vec<(String, MeanCi)> = vec![
+        ("p50".to_string(), metric(&|r| r.p50 as f64)),
+        ("p99.9".to_string(), metric(&|r| r.p999 as f64)),
+        ("p99.99".to_string(), metric(&|r| r.p9999 as f64)),
+        ("throughput".to_string(), metric(&|r| r.throughput)),
+        ("drop_rate".to_string(), metric(&|r| r.drop_rate)),
+    ];
+
+    let classes: &[ClassStats] = runs.first().map_or(&[], |r| &r.classes);
+    for (i, class) in classes.iter().enumerate() {
+        let prefix: String = if classes.len() > 1 { format!("{}.", class.name) } else { String::new() };
+        if classes.len() > 1 {
+            rows.push((format!("{}p50", prefix), metric(&|r| r.classes[i].p50 as f64)));
+            rows.push((format!("{}p99", prefix), metric(&|r| r.classes[i].p99 as f64)));
+            rows.push((format!("{}p99.9", prefix), metric(&|r| r.classes[i].p999 as f64)));
+        }
+        if class.slo.is_some() {
+            rows.push((format!("{}slo_violation_rate", prefix), metric(&|r| r.classes[i].slo_violation_rate().unwrap_or(0.0))));
+        }
+    }
+    rows
+}
@@ -0,0 +1,95 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::rand::Rng;
+use rustc_serialize::json::Json;
+
+use crate::{
+    request::Request,
+    config::{
+        ConfigError,
+        get_opt_string,
+        get_opt_usize,
+        get_opt_bool,
+    },
+    layout::Context,
+    worker_core::Core,
+};
+
+// Quick Explanation of this
+// Work stealing between the cores running the application (Layouts 2, 3 and 4), enabled by the "stealing" section.
+// After every step, each core with nothing to run may take one request waiting in the local queue of a peer
+// (behind the request the peer is running), choosing the victim by "stealing.policy". With "stealing.same_group",
+// a core only steals inside its own group: the application cores of the same network core in Layout 4 (in the other
+// layouts, all cores are a single group). The thief spends "stealing.cost" ticks before starting the stolen request.
+// A core only has something to steal when requests wait in the queue of a busy peer: in Layout 3, the dispatcher must
+// hand them to busy cores (the default "first_idle" only hands them to idle ones, so it is rejected), and in Layout 4
+// the network cores hand them to the least loaded application core of their group when none is idle.
+// Layout 1 is left out (rejected): its workers may be spinning on a flow lock.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StealPolicy {
+    // Any peer with a waiting request
+    Random,
+    // The peer with the most waiting requests (the first one among them)
+    Longest,
+}
+
+// Structure
+#[derive(Debug, Clone)]
+pub struct StealingConfig {
+    pub policy: StealPolicy,
+    pub same_group: bool,
+    pub cost: usize,
+}
+
+// Associate Functions
+impl StealingConfig {
+    // None when the section is missing (no stealing)
+    pub fn from_json(json: &Json) -> Result<Option<StealingConfig>, ConfigError> {
+        if json.find("stealing").is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(StealingConfig {
+            policy: match get_opt_string(json, "stealing.policy")?.as_deref() {
+                None | Some("random") => StealPolicy::Random,
+                Some("longest") => StealPolicy::Longest,
+                Some(other) => return Err(ConfigError::invalid("stealing.policy", format!("should be \"random\" or \"longest\", found \"{}\"", other))),
+            },
+            same_group: get_opt_bool(json, "stealing.same_group")?.unwrap_or(false),
+            cost: get_opt_usize(json, "stealing.cost")?.unwrap_or(0),
+        }))
+    }
+}
+
+// Lets every core of 'groups' with nothing to run steal one waiting request from a peer
+pub fn steal(config: &StealingConfig, groups: &mut [Vec<Core>], ctx: &mut Context) {
+    let cores: Vec<(usize, usize)> = groups.iter().enumerate()
+        .flat_map(|(group, cores)| (0..cores.len()).map(move |idx| (group, idx)))
+        .collect();
+
+    for &(group, idx) in cores.iter() {
+        if !groups[group][idx].can_steal(ctx.t_cur) {
+            continue;
+        }
+
+        let victims: Vec<(usize, usize)> = cores.iter()
+            .filter(|(g, i)| (*g, *i) != (group, idx) && (!config.same_group || *g == group))
+            .filter(|(g, i)| groups[*g][*i].get_nr_waiting() > 0)
+            .cloned()
+            .collect();
+        if victims.is_empty() {
+            continue;
+        }
+
+        let (g, i): (usize, usize) = match config.policy {
+            StealPolicy::Random => victims[ctx.rng.gen_range(0..victims.len())],
+            // Ties go to the first victim in group order ('max_by_key' keeps the last one, hence the 'rev')
+            StealPolicy::Longest => *victims.iter().rev().max_by_key(|(g, i)| groups[*g][*i].get_nr_waiting()).unwrap(),
+        };
+        let req: Request = groups[g][i].pop_stolen();
+        log::warn!("[{:?}]: Core #{:?} stole the Request #{:?} from Core #{:?}", ctx.t_cur, groups[group][idx].get_id(), req.get_id(), groups[g][i].get_id());
+        groups[group][idx].push_stolen(req, config.cost, ctx.t_cur, ctx.events);
+    }
+}
@@ -0,0 +1,99 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    fs::File,
+    path::Path,
+};
+
+use crate::stats::RunSummary;
+
+// Quick Explanation of this
+// A sweep runs the same configuration for several values of one parameter (e.g. packets.rate from 100k to 1M pps),
+// to build load-latency curves. Each point is a full simulation (with all its runs), and the results of all points
+// are written in a single CSV file with one row per point.
+
+// Structure
+pub struct Sweep {
+    pub path: String,
+    pub values: Vec<String>,
+}
+
+// Associate Functions
+impl Sweep {
+    // 'spec' is <key.path=v1,v2,...>, <key.path=[v1,v2,...]> or <key.path=start:end:step> (the end is included)
+    pub fn parse(spec: &str) -> Result<Sweep, String> {
+        let (path, values) = match spec.split_once('=') {
+            Some((path, values)) if !path.is_empty() && !values.is_empty() => (path, values),
+            _ => return Err(format!("'--sweep' expects <key.path=v1,v2,...> or <key.path=start:end:step>, got '{}'", spec)),
+        };
+
+        let values: Vec<String> = if values.contains(':') {
+            parse_range(values)?
+        } else {
+            // The list may be written as a JSON array too (e.g. [1,2,3])
+            let values: &str = values.strip_prefix('[').and_then(|v| v.strip_suffix(']')).unwrap_or(values);
+            values.split(',').map(|v| v.trim().to_string()).collect()
+        };
+
+        Ok(Sweep {
+            path: path.to_string(),
+            values,
+        })
+    }
+}
+
+fn parse_range(range: &str) -> Result<Vec<String>, String> {
+    let bounds: Vec<&str> = range.split(':').collect();
+    if bounds.len() != 3 {
+        return Err(format!("a range should be start:end:step, got '{}'", range));
+    }
+
+    // Integer ranges give integer values, so they can be used for integer fields (e.g. number of cores)
+    if let (Ok(start), Ok(end), Ok(step)) = (bounds[0].parse::<u64>(), bounds[1].parse::<u64>(), bounds[2].parse::<u64>()) {
+        if step == 0 || start > end {
+            return Err(format!("invalid range '{}'", range));
+        }
+        return Ok((start..=end).step_by(step as usize).map(|v| v.to_string()).collect());
+    }
+
+    match (bounds[0].parse::<f64>(), bounds[1].parse::<f64>(), bounds[2].parse::<f64>()) {
+        (Ok(start), Ok(end), Ok(step)) if step > 0.0 && start <= end => {
+            // Counting the steps avoids accumulating rounding errors
+            let nr_points: usize = ((end - start) / step + 1e-9).floor() as usize + 1;
+            Ok((0..nr_points).map(|i| (start + i as f64 * step).to_string()).collect())
+        },
+        _ => Err(format!("invalid range '{}'", range)),
+    }
+}
+
+// One row of the sweep: the value of the parameter and the mean of the metrics over the runs of that point
+pub struct SweepPoint {
+    pub value: String,
+    pub layout: usize,
+    pub offered_load: f64,
+    pub summaries: Vec<RunSummary>,
+}
+
+pub fn print_sweep(path: &str, points: &[SweepPoint], out_dir: &Path) {
+    let mut writer: csv::Writer<File> = csv::Writer::from_path(out_dir.join("sweep.csv")).unwrap();
+
+    writer.write_record([path, "layout", "runs", "offered_load", "throughput", "dropped", "drop_rate", "p50", "p99", "p99.9", "p99.99"]).unwrap();
+    for point in points {
+        let mean = |f: fn(&RunSummary) -> f64| point.summaries.iter().map(f).sum::<f64>() / point.summaries.len() as f64;
+        writer.write_record([
+            point.value.clone(),
+            point.layout.to_string(),
+            point.summaries.len().to_string(),
+            point.offered_load.to_string(),
+            mean(|r| r.throughput).to_string(),
+            mean(|r| r.dropped as f64).to_string(),
+            mean(|r| r.drop_rate).to_string(),
+            mean(|r| r.p50 as f64).to_string(),
+            mean(|r| r.p99 as f64).to_string(),
+            mean(|r| r.p999 as f64).to_string(),
+            mean(|r| r.p9999 as f64).to_string(),
+        ]).unwrap();
+    }
+    writer.flush().unwrap();
+}
@@ -0,0 +1,222 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Imports
+use ::std::{
+    collections::HashMap,
+    fs,
+};
+
+// Quick Explanation of this
+// A trace replays recorded requests instead of generating them from 'packets.rate' and 'packets.distribution'.
+// Each line of the file is one request, in order of arrival:
+//     t_arrival flow_id [stack_time [application_time]]
+// Columns may be separated by commas or spaces (CSV or a text dump of a pcap), and empty lines and lines
+// starting with '#' are ignored. The first line may be a header naming the columns, in any order:
+// t_arrival, flow_id, stack_time, application_time and forwarder_time.
+// A missing service time (column not present, empty or "-") is drawn from the configured distribution.
+// Arrivals are in ticks and relative to the first one, so absolute timestamps can be used as they are.
+// Flow ids may be any number (e.g. a hash of the 4-tuple): they are renumbered from 0, in order of first appearance.
+// The whole file is read and kept in memory before the run.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Arrival,
+    Flow,
+    Stack,
+    Application,
+    Forwarder,
+    Ignored,
+}
+
+impl Column {
+    fn from_name(name: &str) -> Column {
+        match name {
+            "t_arrival" | "arrival" | "timestamp" => Column::Arrival,
+            "flow_id" | "flow" => Column::Flow,
+            "stack_time" | "network_stack" | "stack" => Column::Stack,
+            "application_time" | "application" => Column::Application,
+            "forwarder_time" | "forwarder" => Column::Forwarder,
+            _ => Column::Ignored,
+        }
+    }
+}
+
+// Structure
+#[derive(Debug, Clone, Copy)]
+pub struct TraceRecord {
+    pub t_arrival: usize,
+    pub flow_id: usize,
+    pub stack_time: Option<usize>,
+    pub application_time: Option<usize>,
+    pub forwarder_time: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Trace {
+    pub records: Vec<TraceRecord>,
+}
+
+// Associate Functions
+impl Trace {
+    pub fn from_file(filename: &str) -> Result<Trace, String> {
+        let data: String = fs::read_to_string(filename).map_err(|e| format!("cannot read {}: {}", filename, e))?;
+        Trace::parse(&data).map_err(|e| format!("{}: {}", filename, e))
+    }
+
+    pub fn parse(data: &str) -> Result<Trace, String> {
+        let mut columns: Vec<Column> = vec![Column::Arrival, Column::Flow, Column::Stack, Column::Application];
+        let mut records: Vec<TraceRecord> = Vec::<TraceRecord>::new();
+        // Flow id in the trace -> flow id in the simulation
+        let mut flow_ids: HashMap<usize, usize> = HashMap::<usize, usize>::new();
+
+        let lines = data.lines().enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        for (line, text) in lines {
+            let fields: Vec<&str> = if text.contains(',') {
+                text.split(',').map(|field| field.trim()).collect()
+            } else {
+                text.split_whitespace().collect()
+            };
+
+            // A header starts with a name instead of a timestamp
+            if records.is_empty() && fields[0].parse::<f64>().is_err() {
+                columns = fields.iter().map(|name| Column::from_name(&name.to_lowercase())).collect();
+                for required in [Column::Arrival, Column::Flow] {
+                    if !columns.contains(&required) {
+                        return Err(format!("line {}: the header should name the columns t_arrival and flow_id", line));
+                    }
+                }
+                continue;
+            }
+
+            let mut record: TraceRecord = TraceRecord {
+                t_arrival: 0,
+                flow_id: 0,
+                stack_time: None,
+                application_time: None,
+                forwarder_time: None,
+            };
+            let mut has_arrival: bool = false;
+            let mut has_flow: bool = false;
+            for (column, field) in columns.iter().zip(&fields) {
+                if *column == Column::Ignored || field.is_empty() || *field == "-" {
+                    continue;
+                }
+                let value: usize = parse_field(line, field)?;
+                match column {
+                    Column::Arrival => {
+                        record.t_arrival = value;
+                        has_arrival = true;
+                    },
+                    Column::Flow => {
+                        let nr_flows: usize = flow_ids.len();
+                        record.flow_id = *flow_ids.entry(value).or_insert(nr_flows);
+                        has_flow = true;
+                    },
+                    Column::Stack => record.stack_time = Some(value),
+                    Column::Application => record.application_time = Some(value),
+                    Column::Forwarder => record.forwarder_time = Some(value),
+                    Column::Ignored => {},
+                }
+            }
+            if !has_arrival || !has_flow {
+                return Err(format!("line {}: the arrival time and the flow id are required", line));
+            }
+            if let Some(last) = records.last() {
+                if record.t_arrival < last.t_arrival {
+                    return Err(format!("line {}: the arrival times should not decrease", line));
+                }
+            }
+            records.push(record);
+        }
+
+        // Arrivals relative to the first one
+        let t_first: usize = match records.first() {
+            None => return Err("there is no request".to_string()),
+            Some(record) => record.t_arrival,
+        };
+        for record in records.iter_mut() {
+            record.t_arrival -= t_first;
+        }
+
+        Ok(Trace { records })
+    }
+
+    // Average arrival rate of the trace, in pps
+    pub fn rate(&self) -> f64 {
+        let span: usize = self.records.last().map_or(0, |record| record.t_arrival);
+        if span == 0 {
+            return 0.0;
+        }
+        (self.records.len() - 1) as f64 * 1_000_000_000.0 / span as f64
+    }
+
+    // Number of different flows (they are numbered from 0)
+    pub fn nr_flows(&self) -> usize {
+        self.records.iter().map(|record| record.flow_id + 1).max().unwrap_or(1)
+    }
+}
+
+fn parse_field(line: usize, field: &str) -> Result<usize, String> {
+    // Timestamps may come with a fraction (e.g. from a pcap dump), which is truncated to ticks
+    match field.parse::<u64>() {
+        Ok(value) => Ok(value as usize),
+        Err(_) => match field.parse::<f64>() {
+            Ok(value) if value.is_finite() && value >= 0.0 => Ok(value as usize),
+            _ => Err(format!("line {}: '{}' is not a non-negative number", line, field)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_without_a_header() {
+        let trace: Trace = Trace::parse("# t_arrival flow_id stack_time application_time\n1000 0 800 2000\n1500 1 - 3000\n\n1500 0\n").unwrap();
+        let records: Vec<(usize, usize, Option<usize>, Option<usize>)> = trace.records.iter()
+            .map(|record| (record.t_arrival, record.flow_id, record.stack_time, record.application_time))
+            .collect();
+        assert_eq!(records, vec![(0, 0, Some(800), Some(2000)), (500, 1, None, Some(3000)), (500, 0, None, None)]);
+        assert!(trace.records.iter().all(|record| record.forwarder_time.is_none()));
+        assert_eq!(trace.nr_flows(), 2);
+    }
+
+    #[test]
+    fn parses_a_header_in_any_order() {
+        let trace: Trace = Trace::parse("flow,src_ip,timestamp,application_time\n7,10.0.0.1,2000.9,\n3,10.0.0.2,2500,100\n").unwrap();
+        let records: Vec<(usize, usize, Option<usize>)> = trace.records.iter()
+            .map(|record| (record.t_arrival, record.flow_id, record.application_time))
+            .collect();
+        assert_eq!(records, vec![(0, 0, None), (500, 1, Some(100))]);
+    }
+
+    #[test]
+    fn arrivals_are_relative_to_the_first_one() {
+        let trace: Trace = Trace::parse("1700000000000000000 1\n1700000000000001000 1\n1700000000000003000 2\n").unwrap();
+        let arrivals: Vec<usize> = trace.records.iter().map(|record| record.t_arrival).collect();
+        assert_eq!(arrivals, vec![0, 1000, 3000]);
+        assert_eq!(trace.rate(), 2.0 * 1_000_000_000.0 / 3000.0);
+    }
+
+    #[test]
+    fn sparse_flow_ids_are_renumbered() {
+        let trace: Trace = Trace::parse("0 3735928559\n10 42\n20 3735928559\n30 18446744073709551615\n").unwrap();
+        let flows: Vec<usize> = trace.records.iter().map(|record| record.flow_id).collect();
+        assert_eq!(flows, vec![0, 1, 0, 2]);
+        assert_eq!(trace.nr_flows(), 3);
+    }
+
+    #[test]
+    fn malformed_lines_name_their_line() {
+        let error = |data: &str| Trace::parse(data).unwrap_err();
+        assert_eq!(error("0 1\n# comment\n10 x\n"), "line 3: 'x' is not a non-negative number");
+        assert_eq!(error("0 1\n10 1 -5\n"), "line 2: '-5' is not a non-negative number");
+        assert_eq!(error("0 1\n\n20\n"), "line 3: the arrival time and the flow id are required");
+        assert_eq!(error("20 1\n10 1\n"), "line 2: the arrival times should not decrease");
+        assert_eq!(error("time,stack_time\n0,5\n"), "line 1: the header should name the columns t_arrival and flow_id");
+        assert_eq!(error("t_arrival flow_id\n"), "there is no request");
+    }
+}
@@ -1,24 +1,65 @@
 // Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
 
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+};
 
 // Imports
 use crate::{
     Request,
     CoreState,
     CoreAction,
+    event::{
+        EventKind,
+        EventQueue,
+    },
+    discipline::QueueDiscipline,
 };
 
 // Quick Explanation of this
+// A core is only scheduled on the ticks where something may change. Whenever a core starts a stage,
+// it pushes the tick where that stage completes to the event queue; whenever it has a request waiting
+// in its local queue, it asks to be scheduled again in the next tick.
+// The response of a request with a TX stage is sent by the same core in Layouts 1 and 2, after the application.
+// In Layouts 3 and 4, the application core hands it back to a network core, which sends the queued responses
+// before receiving new requests. A request only departs when its TX stage (if any) completes.
+// The next request of the local queue is chosen by the 'QueueDiscipline' of the core, which may also preempt the
+// running request at the end of its quantum (a response being sent is never preempted). A preempted request keeps
+// its flow lock (Layout 1), and goes back to the local queue even if it is full.
+// A core that steals a request (see 'stealing') spends the cost of the steal as a context switch.
 
 // Structure
 pub struct Core {
     core_id: usize,
     is_idle: bool,
     action: CoreAction,
+    queue_size: usize,
+    discipline: Arc<dyn QueueDiscipline>,
+    // End of the quantum of the running request
+    t_slice_end: usize,
+    // The core is switching context (after a preemption) till this tick
+    t_switch_end: usize,
     current_request: Option<Request>,
     local_queue: VecDeque<Request>,
     ready_queue: VecDeque<Request>,
+    // Responses waiting for a network core (Layouts 3 and 4)
+    tx_queue: VecDeque<Request>,
+    nr_finished: usize,
+    nr_dropped: usize,
+    nr_preemptions: usize,
+    nr_steals: usize,
+}
+
+// Counters reported by each core at the end of the simulation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoreStats {
+    pub core_id: usize,
+    pub finished: usize,
+    pub dropped: usize,
+    pub preemptions: usize,
+    // Requests stolen by this core from its peers
+    pub steals: usize,
 }
 
 impl Core {
@@ -26,37 +67,200 @@ impl Core {
         core_id: usize,
         action: CoreAction,
         queue_size: usize,
+        discipline: Arc<dyn QueueDiscipline>,
     ) -> Core {
         let local_queue: VecDeque<Request> = VecDeque::<Request>::with_capacity(queue_size);
         let ready_queue: VecDeque<Request> = VecDeque::<Request>::with_capacity(queue_size);
 
-        let core: Core = Core {
+        Core {
             core_id,
             is_idle: true,
             action,
+            queue_size,
+            discipline,
+            t_slice_end: 0,
+            t_switch_end: 0,
             current_request: None,
             local_queue,
             ready_queue,
-        };
-
-        core
+            tx_queue: VecDeque::<Request>::new(),
+            nr_finished: 0,
+            nr_dropped: 0,
+            nr_preemptions: 0,
+            nr_steals: 0,
+        }
     }
 
     pub fn is_idle(&self) -> bool {
         self.is_idle
     }
 
-    pub fn schedule(&mut self, t_cur: usize, locks: Option<&mut Vec<usize>>) -> CoreState {
+    // Starts the network stack processing of a request on a core that also runs the application (Layouts 1 and 2).
+    fn start_network_stack(core_id: usize, req: &mut Request, t_cur: usize, events: &mut EventQueue) {
+        req.set_p_start(t_cur);
+        if req.p_schedule(t_cur) {
+            // The network stack took a single tick, so the application starts in the next round
+            req.set_r_start(t_cur + 1);
+            events.push(req.get_r_deadline(), EventKind::StageComplete(core_id));
+        } else {
+            events.push(req.get_p_deadline(), EventKind::StageComplete(core_id));
+        }
+    }
+
+    // Starts a request on a core that runs the network stack, the application and the TX (Layouts 1 and 2),
+    // or resumes it from the stage where it was preempted
+    fn start_worker(core_id: usize, req: &mut Request, t_cur: usize, events: &mut EventQueue) {
+        if !req.is_p_completed() {
+            Core::start_network_stack(core_id, req, t_cur, events);
+        } else if !req.is_r_completed() {
+            req.set_r_start(t_cur);
+            events.push(req.get_r_deadline(), EventKind::StageComplete(core_id));
+        } else {
+            req.set_t_start(t_cur);
+            events.push(req.get_t_deadline(), EventKind::StageComplete(core_id));
+        }
+    }
+
+    // Ticks left of 'req' in a core running 'action'
+    fn remaining(action: &CoreAction, req: &Request) -> usize {
+        match action {
+            CoreAction::Forward => req.get_forward_left(),
+            CoreAction::Application => req.get_request_left(),
+            CoreAction::NetworkStack if req.is_r_completed() => req.get_tx_left(),
+            CoreAction::NetworkStack => req.get_stack_left(),
+            CoreAction::NetworkStackAndApplication | CoreAction::NetworkStackAndApplicationLock => {
+                let mut remaining: usize = req.get_tx_left();
+                if !req.is_r_completed() {
+                    remaining += req.get_request_left();
+                }
+                if !req.is_p_completed() {
+                    remaining += req.get_stack_left();
+                }
+                remaining
+            },
+        }
+    }
+
+    // The next request of the local queue, as chosen by the discipline, starting its quantum
+    fn pop_next(&mut self, t_cur: usize, events: &mut EventQueue) -> Option<Request> {
+        if self.local_queue.is_empty() {
+            return None;
+        }
+        let action: &CoreAction = &self.action;
+        let idx: usize = self.discipline.select(&self.local_queue, &|req| Core::remaining(action, req));
+        if let Some(quantum) = self.discipline.quantum() {
+            self.t_slice_end = t_cur + quantum;
+            events.push(self.t_slice_end, EventKind::StageComplete(self.core_id));
+        }
+        self.local_queue.remove(idx)
+    }
+
+    // At the end of the quantum of the running request, gives the core to the next request of the local queue
+    // if the discipline says so (or else starts a new quantum)
+    fn try_preempt(&mut self, t_cur: usize, locks: Option<&Vec<usize>>, events: &mut EventQueue) {
+        let quantum: usize = match self.discipline.quantum() {
+            Some(quantum) if t_cur >= self.t_slice_end => quantum,
+            _ => return,
+        };
+        let forwarding: bool = matches!(self.action, CoreAction::Forward);
+        let req: &mut Request = match &mut self.current_request {
+            // The responses are sent to completion
+            Some(req) if !(matches!(self.action, CoreAction::NetworkStack) && req.is_r_completed()) => req,
+            _ => return,
+        };
+
+        // A request waiting for the lock of its flow did not start yet
+        let holds_lock: bool = match &locks {
+            Some(spinlocks) => spinlocks[req.get_flow_id()] == self.core_id,
+            None => true,
+        };
+        if holds_lock && !req.checkpoint(t_cur, forwarding) {
+            // Its stage completes now, so we decide in the next round (if it does not depart)
+            events.push(t_cur + 1, EventKind::StageComplete(self.core_id));
+            return;
+        }
+
+        let action: &CoreAction = &self.action;
+        let remaining = |req: &Request| Core::remaining(action, req);
+        let preempts: bool = !self.local_queue.is_empty() && {
+            let next: &Request = &self.local_queue[self.discipline.select(&self.local_queue, &remaining)];
+            self.discipline.preempts(req, next, &remaining)
+        };
+        if !preempts {
+            self.t_slice_end = t_cur + quantum;
+            events.push(self.t_slice_end, EventKind::StageComplete(self.core_id));
+            return;
+        }
+
+        // The flow lock (if any) stays with the preempted request
+        let mut req: Request = self.current_request.take().unwrap();
+        if holds_lock {
+            req.set_preempted();
+        }
+        self.local_queue.push_back(req);
+        self.t_switch_end = t_cur + self.discipline.context_switch();
+        self.nr_preemptions += 1;
+    }
+
+    // Makes progress on a request that completed the network stack on a core that also runs the application
+    // (Layouts 1 and 2): the application, then the TX stage from the next round, if any.
+    // Returns true when the request departs.
+    fn run_application(core_id: usize, req: &mut Request, t_cur: usize, events: &mut EventQueue) -> bool {
+        if req.is_r_completed() {
+            // It means that we are sending the response
+            if req.t_schedule(t_cur) {
+                req.set_departure_time(t_cur + 1);
+                return true;
+            }
+            return false;
+        }
+
+        if !req.r_schedule(t_cur) {
+            // It means that we still need process the request through application processing
+            return false;
+        }
+        if req.get_tx_time() == 0 {
+            req.set_departure_time(t_cur + 1);
+            return true;
+        }
+        req.set_t_start(t_cur + 1);
+        events.push(req.get_t_deadline(), EventKind::StageComplete(core_id));
+        false
+    }
+
+    // The completion of a stage started at 't_cur' is only noticed in the next rounds, at the earliest.
+    fn wait_stage(&self, deadline: usize, t_cur: usize, events: &mut EventQueue) {
+        events.push(deadline.max(t_cur + 1), EventKind::StageComplete(self.core_id));
+    }
+
+    pub fn schedule(&mut self, t_cur: usize, locks: Option<&mut Vec<usize>>, events: &mut EventQueue) -> CoreState {
+        self.try_preempt(t_cur, locks.as_deref(), events);
+        if self.current_request.is_none() && t_cur < self.t_switch_end {
+            // Switching context after a preemption
+            self.is_idle = false;
+            events.push(self.t_switch_end, EventKind::Enqueue(self.core_id));
+            return CoreState::Running;
+        }
+
+        let state: CoreState = self.make_progress(t_cur, locks, events);
+
+        // A request is waiting in the local queue, so we must be scheduled again in the next round
+        if self.current_request.is_none() && !(self.local_queue.is_empty() && self.tx_queue.is_empty()) {
+            events.push(t_cur + 1, EventKind::Enqueue(self.core_id));
+        }
+
+        state
+    }
+
+    fn make_progress(&mut self, t_cur: usize, locks: Option<&mut Vec<usize>>, events: &mut EventQueue) -> CoreState {
         match self.action {
             CoreAction::Forward => {
                 // Layout 1
                 // In this case, we need only receive the packet and forward to an idle core
                 match &mut self.current_request {
                     Some(req) => {
-                        if req.f_schedule() {
-                            let mut request: Request = self.current_request.take().unwrap();
-                            self.current_request = None;
-                            request.set_f_end(t_cur);
+                        if req.f_schedule(t_cur) {
+                            let request: Request = self.current_request.take().unwrap();
                             self.is_idle = true;
                             CoreState::Finished(request)
                         } else {
@@ -65,9 +269,10 @@ impl Core {
                         }
                     },
                     None => {
-                        if let Some(mut req) = self.local_queue.pop_front() {
+                        if let Some(mut req) = self.pop_next(t_cur, events) {
                             req.set_f_start(t_cur);
-                            req.f_schedule(); // We assume that application time bigger than 1, that why we do not check if application completed
+                            req.f_schedule(t_cur); // We assume that application time bigger than 1, that why we do not check if application completed
+                            self.wait_stage(req.get_f_deadline(), t_cur, events);
                             self.current_request = Some(req);
                             self.is_idle = false;
                             CoreState::Running
@@ -91,12 +296,12 @@ impl Core {
                 // In this case, we need to process only the application
                 match &mut self.current_request {
                     Some(req) => {
-                        if req.r_schedule() {
-                            // If 'req' completed the application, we can finalize it (it will be forward to another core)
+                        if req.r_schedule(t_cur) {
+                            // If 'req' completed the application, we can finalize it (or its response goes back to a network core)
                             let mut request: Request = self.current_request.take().unwrap();
-                            self.current_request = None;
-                            request.set_r_end(t_cur);
-                            request.set_departure_time(t_cur + 1);
+                            if request.get_tx_time() == 0 {
+                                request.set_departure_time(t_cur + 1);
+                            }
                             self.is_idle = true;
                             CoreState::Finished(request)
                         } else {
@@ -105,9 +310,10 @@ impl Core {
                         }
                     },
                     None => {
-                        if let Some(mut req) = self.local_queue.pop_front() {
+                        if let Some(mut req) = self.pop_next(t_cur, events) {
                             req.set_r_start(t_cur);
-                            req.r_schedule(); // We assume that application time bigger than 1, that why we do not check if application completed
+                            req.r_schedule(t_cur); // We assume that application time bigger than 1, that why we do not check if application completed
+                            self.wait_stage(req.get_r_deadline(), t_cur, events);
                             self.current_request = Some(req);
                             self.is_idle = false;
                             CoreState::Running
@@ -122,12 +328,22 @@ impl Core {
                 // Layouts 3 and 4
                 // In this case, we need to process only the network stack
                 match &mut self.current_request {
+                    Some(req) if req.is_r_completed() => {
+                        // It means that we are sending the response
+                        if req.t_schedule(t_cur) {
+                            let mut request: Request = self.current_request.take().unwrap();
+                            request.set_departure_time(t_cur + 1);
+                            self.is_idle = true;
+                            CoreState::Finished(request)
+                        } else {
+                            self.is_idle = false;
+                            CoreState::Running
+                        }
+                    },
                     Some(req) => {
-                        if req.p_schedule() {
+                        if req.p_schedule(t_cur) {
                             // If 'req' completed the network stack, we can finalize it (it will be forward to another core)
-                            let mut request: Request = self.current_request.take().unwrap();
-                            self.current_request = None;
-                            request.set_p_end(t_cur);
+                            let request: Request = self.current_request.take().unwrap();
                             self.is_idle = true;
                             CoreState::Finished(request)
                         } else {
@@ -136,9 +352,18 @@ impl Core {
                         }
                     },
                     None => {
-                        if let Some(mut req) = self.local_queue.pop_front() {
+                        if let Some(mut req) = self.tx_queue.pop_front() {
+                            // The responses are sent before receiving new requests
+                            req.set_t_start(t_cur);
+                            req.t_schedule(t_cur);
+                            self.wait_stage(req.get_t_deadline(), t_cur, events);
+                            self.current_request = Some(req);
+                            self.is_idle = false;
+                            CoreState::Running
+                        } else if let Some(mut req) = self.pop_next(t_cur, events) {
                             req.set_p_start(t_cur);
-                            req.p_schedule(); //We assume that network stack time bigger than 1, that why we do not check if network stack completed
+                            req.p_schedule(t_cur); //We assume that network stack time bigger than 1, that why we do not check if network stack completed
+                            self.wait_stage(req.get_p_deadline(), t_cur, events);
                             self.current_request = Some(req);
                             self.is_idle = false;
                             CoreState::Running
@@ -156,35 +381,29 @@ impl Core {
                     Some(req) => {
                         if req.is_p_completed() {
                             // If 'req' completed network stack processing, we can go to the application processing
-                            if req.r_schedule() {
-                                // If 'req' completed both network stack and application processing, we can finalize it
-                                let mut request: Request = self.current_request.take().unwrap();
-                                self.current_request = None;
-                                
-                                request.set_r_end(t_cur);
-                                request.set_departure_time(t_cur + 1);
+                            if Core::run_application(self.core_id, req, t_cur, events) {
+                                // If 'req' completed the network stack, the application and the TX, we can finalize it
+                                let request: Request = self.current_request.take().unwrap();
                                 self.is_idle = true;
                                 CoreState::Finished(request)
                             } else {
-                                // It means that we still need process the request through application processing
                                 self.is_idle = false;
                                 CoreState::Running
                             }
                         } else {
                             // It means that we still need process the request through network stack processing
-                            if req.p_schedule() {
+                            if req.p_schedule(t_cur) {
                                 // If 'req' completed right now, we set the application request in the next round
-                                req.set_p_end(t_cur);
                                 req.set_r_start(t_cur + 1);
+                                events.push(req.get_r_deadline(), EventKind::StageComplete(self.core_id));
                             }
                             self.is_idle = false;
                             CoreState::Running
                         }
                     },
                     None => {
-                        if let Some(mut req) = self.local_queue.pop_front() {
-                            req.set_p_start(t_cur);
-                            req.p_schedule(); //We assume that network stack time bigger than 1, that why we do not check if network stack completed
+                        if let Some(mut req) = self.pop_next(t_cur, events) {
+                            Core::start_worker(self.core_id, &mut req, t_cur, events);
                             self.current_request = Some(req);
                             self.is_idle = false;
                             CoreState::Running
@@ -204,51 +423,58 @@ impl Core {
                         if spinlocks[req.get_flow_id()] == usize::MAX {
                             // This means that will be the first time that this core will process this request
                             spinlocks[req.get_flow_id()] = self.core_id;
-                            req.set_p_start(t_cur);
-                            req.p_schedule(); //We assume that network stack time bigger than 1, that why we do not check if network stack completed
+                            Core::start_worker(self.core_id, req, t_cur, events);
                             self.is_idle = false;
                             CoreState::Running
                         } else if spinlocks[req.get_flow_id()] == self.core_id {
                             if req.is_p_completed() {
                                 // If 'req' completed network stack processing, we can go to the application processing
-                                if req.r_schedule() {
-                                    // If 'req' completed both network stack and application processing, we can finalize it
-                                    let mut request: Request = self.current_request.take().unwrap();
-                                    self.current_request = None;
-                                    request.set_r_end(t_cur);
-                                    request.set_departure_time(t_cur + 1);
+                                if Core::run_application(self.core_id, req, t_cur, events) {
+                                    // If 'req' completed the network stack, the application and the TX, we can finalize it
+                                    // (the flow lock is held till the response is sent)
+                                    let request: Request = self.current_request.take().unwrap();
                                     self.is_idle = true;
                                     spinlocks[request.get_flow_id()] = usize::MAX;
+                                    events.push(t_cur + 1, EventKind::LockRelease(request.get_flow_id()));
                                     CoreState::Finished(request)
                                 } else {
-                                    // It means that we still need process the request through application processing
                                     self.is_idle = false;
                                     CoreState::Running
                                 }
                             } else {
                                 // It means that we still need process the request through network stack processing
-                                if req.p_schedule() {
+                                if req.p_schedule(t_cur) {
                                     // If 'req' completed right now, we set the application request in the next round
-                                    req.set_p_end(t_cur);
                                     req.set_r_start(t_cur + 1);
+                                    events.push(req.get_r_deadline(), EventKind::StageComplete(self.core_id));
                                 }
                                 self.is_idle = false;
                                 CoreState::Running
                             }
                         } else {
-                            // Another worker is holding the lock for this request
+                            // Another worker is holding the lock for this request (we will retry when it is released)
                             self.is_idle = false;
                             CoreState::Running
                         }
                     },
                     None => {
-                        if let Some(mut req) = self.local_queue.pop_front() {
+                        if let Some(mut req) = self.pop_next(t_cur, events) {
                             let spinlocks: &mut Vec<usize> = locks.unwrap();
-                            if spinlocks[req.get_flow_id()] == usize::MAX {
+                            if spinlocks[req.get_flow_id()] != usize::MAX && !req.is_preempted() {
+                                // Waiting for a lock while holding the one of a preempted request could deadlock
+                                // two workers (or keep a request of the same flow waiting forever), so that one resumes first
+                                if let Some(idx) = self.local_queue.iter().position(|other| other.is_preempted()) {
+                                    let held: Request = self.local_queue.remove(idx).unwrap();
+                                    self.local_queue.push_front(std::mem::replace(&mut req, held));
+                                }
+                            }
+                            if req.is_preempted() {
+                                // This core still holds the lock of its flow
+                                Core::start_worker(self.core_id, &mut req, t_cur, events);
+                            } else if spinlocks[req.get_flow_id()] == usize::MAX {
                                 // This indicates that no worker is processing this flow
                                 spinlocks[req.get_flow_id()] = self.core_id;
-                                req.set_p_start(t_cur);
-                                req.p_schedule(); //We assume that network stack time bigger than 1, that why we do not check if network stack completed
+                                Core::start_worker(self.core_id, &mut req, t_cur, events);
                             }
                             self.current_request = Some(req);
                             self.is_idle = false;
@@ -267,27 +493,107 @@ impl Core {
         self.core_id
     }
 
-    pub fn get_action(&self) -> &CoreAction {
-        &self.action
+    pub fn add_finished(&mut self) {
+        self.nr_finished += 1;
+    }
+
+    pub fn add_dropped(&mut self) {
+        self.nr_dropped += 1;
+    }
+
+    pub fn get_stats(&self) -> CoreStats {
+        CoreStats {
+            core_id: self.core_id,
+            finished: self.nr_finished,
+            dropped: self.nr_dropped,
+            preemptions: self.nr_preemptions,
+            steals: self.nr_steals,
+        }
     }
 
-    pub fn try_enqueue(&mut self, req: Request) -> Result<(), Request> {
-        if self.local_queue.len() < self.local_queue.capacity() {
+    #[allow(clippy::result_large_err)]
+    pub fn try_enqueue(&mut self, req: Request, t_cur: usize, events: &mut EventQueue) -> Result<(), Request> {
+        if self.local_queue.len() < self.queue_size {
             self.local_queue.push_back(req);
+            events.push(t_cur + 1, EventKind::Enqueue(self.core_id));
             return Ok(())
         }
         Err(req)
     }
 
+    #[allow(clippy::result_large_err)]
     pub fn try_enqueue_ready_queue(&mut self, req: Request) -> Result<(), Request> {
-        if self.ready_queue.len() < self.ready_queue.capacity() {
+        if self.ready_queue.len() < self.queue_size {
             self.ready_queue.push_back(req);
             return Ok(())
         }
         Err(req)
     }
 
+    // A response to be sent by this network core, from the next round
+    pub fn enqueue_tx(&mut self, req: Request, t_cur: usize, events: &mut EventQueue) {
+        self.tx_queue.push_back(req);
+        events.push(t_cur + 1, EventKind::Enqueue(self.core_id));
+    }
+
+    // Whether the core has nothing to run, so it may steal a request from a peer
+    pub fn can_steal(&self, t_cur: usize) -> bool {
+        self.current_request.is_none() && self.local_queue.is_empty() && self.tx_queue.is_empty() && t_cur >= self.t_switch_end
+    }
+
+    // Requests of the local queue that would wait for another one to run (a free core runs the first one next)
+    pub fn get_nr_waiting(&self) -> usize {
+        match self.current_request {
+            Some(_) => self.local_queue.len(),
+            None => self.local_queue.len().saturating_sub(1),
+        }
+    }
+
+    // The request taken by a thief: the last one, so the others keep their order
+    pub fn pop_stolen(&mut self) -> Request {
+        self.local_queue.pop_back().unwrap()
+    }
+
+    // A request stolen from a peer, started after 'cost' ticks
+    pub fn push_stolen(&mut self, req: Request, cost: usize, t_cur: usize, events: &mut EventQueue) {
+        self.local_queue.push_back(req);
+        self.t_switch_end = t_cur + cost;
+        self.is_idle = false;
+        self.nr_steals += 1;
+        events.push(self.t_switch_end.max(t_cur + 1), EventKind::Enqueue(self.core_id));
+    }
+
     pub fn pop_ready_queue(&mut self) -> Request {
         self.ready_queue.pop_front().unwrap()
     }
-}
\ No newline at end of file
+
+    pub fn get_current_request(&self) -> Option<&Request> {
+        self.current_request.as_ref()
+    }
+
+    // Every request in this core: running, waiting in its queues or to be sent
+    pub fn requests(&self) -> impl Iterator<Item = &Request> {
+        self.current_request.iter()
+            .chain(self.local_queue.iter())
+            .chain(self.ready_queue.iter())
+            .chain(self.tx_queue.iter())
+    }
+
+    pub fn is_ready_queue_empty(&self) -> bool {
+        self.ready_queue.is_empty()
+    }
+
+    // Requests running or waiting in this core
+    pub fn get_load(&self) -> usize {
+        self.local_queue.len() + self.current_request.is_some() as usize
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.local_queue.len() >= self.queue_size
+    }
+
+    // Asks to be scheduled in the next round
+    pub fn wake_up(&self, t_cur: usize, events: &mut EventQueue) {
+        events.push(t_cur + 1, EventKind::Enqueue(self.core_id));
+    }
+}
@@ -0,0 +1,119 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+// Each integration test only uses some of the helpers
+#![allow(dead_code)]
+
+// Imports
+use rustc_serialize::json::Json;
+
+use sim::{
+    ConfigError,
+    SimConfig,
+    Simulation,
+    SimulationResults,
+};
+
+// Quick Explanation of this
+// The configuration shared by the integration tests: 8 cores with a section for every layout, exponential service
+// times and arrivals, and a duration long enough for every request to leave the server. A test changes the layout,
+// the rate, the engine, the queue size and the number of requests, and merges any other field with 'extra'
+// (the objects are merged field by field, so '{"packets": {"nr_flows": 1}}' only changes the number of flows).
+
+// An application that keeps the server well above what it handles at 2 Mpps, so the queues fill up
+pub const OVERLOAD: &str = r#"{"application": {"distribution": "bimodal", "mean1": 1000, "mean2": 100000, "mode": 0.9}}"#;
+
+// Structure
+pub struct TestConfig {
+    layout: usize,
+    rate: usize,
+    engine: &'static str,
+    queue_size: usize,
+    nr_packets: usize,
+    extra: Vec<Json>,
+}
+
+// Associate Functions
+impl TestConfig {
+    pub fn new(layout: usize) -> TestConfig {
+        TestConfig {
+            layout,
+            rate: 2000000,
+            engine: "event",
+            queue_size: 8,
+            nr_packets: 2000,
+            extra: Vec::<Json>::new(),
+        }
+    }
+
+    pub fn rate(mut self, rate: usize) -> TestConfig {
+        self.rate = rate;
+        self
+    }
+
+    pub fn engine(mut self, engine: &'static str) -> TestConfig {
+        self.engine = engine;
+        self
+    }
+
+    pub fn queue_size(mut self, queue_size: usize) -> TestConfig {
+        self.queue_size = queue_size;
+        self
+    }
+
+    pub fn nr_packets(mut self, nr_packets: usize) -> TestConfig {
+        self.nr_packets = nr_packets;
+        self
+    }
+
+    // Merged over the configuration (and over the previous ones), in order
+    pub fn extra(mut self, extra: &str) -> TestConfig {
+        self.extra.push(Json::from_str(extra).unwrap_or_else(|e| panic!("extra should be valid JSON ({}): {}", e, extra)));
+        self
+    }
+
+    pub fn to_json(&self) -> Json {
+        let text: String = format!(r#"{{
+            "layout": {}, "engine": "{}", "duration": 1000000000, "queue_size": {}, "rtt_base": 8000,
+            "nr_total_cores": 8, "nr_indirection_table_entries": 128,
+            "layout1": {{"nr_worker_cores": 7}}, "layout2": {{"nr_worker_cores": 8}},
+            "layout3": {{"nr_application_cores": 7}}, "layout4": {{"nr_network_cores": 2, "nr_application_cores": 6}},
+            "forwarder": {{"distribution": "exponential", "mean1": 300}},
+            "network_stack": {{"distribution": "exponential", "mean1": 800}},
+            "application": {{"distribution": "exponential", "mean1": 2000}},
+            "packets": {{"rate": {}, "nr_packets": {}, "nr_flows": 16, "distribution": "exponential"}}
+        }}"#, self.layout, self.engine, self.queue_size, self.rate, self.nr_packets);
+        let mut json: Json = Json::from_str(&text).expect("valid JSON");
+        for extra in self.extra.iter() {
+            merge(&mut json, extra);
+        }
+        json
+    }
+
+    pub fn try_build(&self) -> Result<SimConfig, ConfigError> {
+        SimConfig::from_json(&self.to_json())
+    }
+
+    pub fn build(&self) -> SimConfig {
+        self.try_build().expect("valid configuration")
+    }
+}
+
+fn merge(json: &mut Json, extra: &Json) {
+    match (json, extra) {
+        (Json::Object(object), Json::Object(fields)) => {
+            for (key, value) in fields.iter() {
+                match object.get_mut(key) {
+                    Some(field) => merge(field, value),
+                    None => {
+                        object.insert(key.clone(), value.clone());
+                    },
+                }
+            }
+        },
+        (json, extra) => *json = extra.clone(),
+    }
+}
+
+pub fn run(run_id: usize, config: &SimConfig) -> SimulationResults {
+    Simulation::new(run_id, config, false).expect("valid simulation").run().expect("no broken invariant")
+}
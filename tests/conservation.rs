@@ -0,0 +1,116 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+mod common;
+
+// Imports
+use sim::{
+    SimulationResults,
+    results::DropStage,
+};
+
+use common::{
+    OVERLOAD,
+    TestConfig,
+};
+
+// Quick Explanation of this
+// Every generated request must end up in exactly one outcome (finished or dropped), in every layout and with every
+// policy of the dispatcher when no worker can take a request. The runs are audited, so the invariants of the server
+// also hold after every step.
+
+const NR_PACKETS: usize = 2000;
+
+// The overloaded server of every test, audited
+fn config(layout: usize) -> TestConfig {
+    TestConfig::new(layout).nr_packets(NR_PACKETS).extra(OVERLOAD).extra(r#"{"audit": true}"#)
+}
+
+fn run(config: TestConfig) -> SimulationResults {
+    common::run(0, &config.build())
+}
+
+// Checks that each request is either finished or dropped, and only once
+fn check_conservation(results: &SimulationResults) {
+    let mut outcomes: Vec<usize> = vec![0; results.nr_packets];
+    for id in results.finished.iter().map(|record| record.id).chain(results.dropped.iter().map(|record| record.id)) {
+        outcomes[id] += 1;
+    }
+    for (id, nr_outcomes) in outcomes.iter().enumerate() {
+        assert_eq!(*nr_outcomes, 1, "layout {}: request #{} has {} outcomes", results.layout_id, id, nr_outcomes);
+    }
+    assert_eq!(results.finished.len() + results.dropped.len(), results.nr_packets);
+}
+
+#[test]
+fn every_request_has_one_outcome_in_every_layout() {
+    for layout in 1..=4 {
+        let results: SimulationResults = run(config(layout));
+        check_conservation(&results);
+        assert!(!results.dropped.is_empty(), "layout {}: the server should be overloaded", layout);
+    }
+}
+
+#[test]
+fn layout1_accounts_for_requests_without_an_idle_worker() {
+    for when_busy in ["buffer", "retry", "drop"] {
+        let extra: String = format!(r#"{{"dispatcher": {{"policy": "first_idle", "when_busy": "{}"}}}}"#, when_busy);
+        let results: SimulationResults = run(config(1).extra(&extra));
+        check_conservation(&results);
+
+        // Only the dispatcher that drops records the requests that no worker could take
+        let nr_dispatcher_drops: usize = results.nr_dropped_at(DropStage::Dispatcher);
+        match when_busy {
+            "drop" => assert!(nr_dispatcher_drops > 0, "{}: no request was dropped by the dispatcher", when_busy),
+            "retry" => assert_eq!(nr_dispatcher_drops, 0, "{}: the forwarder holds the request", when_busy),
+            _ => {},
+        }
+    }
+}
+
+#[test]
+fn every_dispatch_policy_keeps_the_requests() {
+    for policy in ["first_idle", "round_robin", "random", "jsq", "power_of_two", "jbsq"] {
+        for layout in [1, 3] {
+            let extra: String = format!(r#"{{"dispatcher": {{"policy": "{}", "k": 2}}}}"#, policy);
+            check_conservation(&run(config(layout).extra(&extra)));
+        }
+    }
+}
+
+#[test]
+fn every_discipline_keeps_the_requests() {
+    // The requests are queued behind busy cores (except in Layout 4, where the application cores take one at a time),
+    // and the preempted requests of Layout 1 hold their flow lock meanwhile
+    for policy in ["fcfs", "rr", "srpt", "priority", "ps"] {
+        for layout in 1..=4 {
+            let extra: String = format!(r#"{{"dispatcher": {{"policy": "jsq"}}, "discipline": {{"policy": "{}", "quantum": 2000, "context_switch": 50}}}}"#, policy);
+            let mut config: TestConfig = config(layout).extra(&extra);
+            if policy == "ps" {
+                // The core switches between its requests every tick, so fewer and shorter ones to keep the run short
+                config = config.nr_packets(50).extra(r#"{"application": {"mean2": 20000}}"#);
+            }
+            let results: SimulationResults = run(config);
+            check_conservation(&results);
+
+            let nr_preemptions: usize = results.core_stats.iter().map(|stats| stats.preemptions).sum();
+            match policy {
+                // A single class, so every request has the same priority
+                "fcfs" | "priority" => assert_eq!(nr_preemptions, 0, "layout {}, {}: a request was preempted", layout, policy),
+                _ if layout < 4 => assert!(nr_preemptions > 0, "layout {}, {}: no request was preempted", layout, policy),
+                _ => {},
+            }
+        }
+    }
+}
+
+#[test]
+fn stages_of_one_tick_pass_the_audit() {
+    // They complete when they start, but the cores only notice it in the next round
+    for layout in 1..=4 {
+        let extra: &str = r#"{
+            "forwarder": {"mean1": 1}, "network_stack": {"mean1": 1}, "application": {"mean1": 1},
+            "tx_stack": {"distribution": "constant", "mean1": 1}
+        }"#;
+        check_conservation(&run(config(layout).extra(extra)));
+    }
+}
@@ -0,0 +1,36 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+mod common;
+
+// Imports
+use sim::{
+    SimConfig,
+    SimulationResults,
+};
+
+use common::TestConfig;
+
+// Quick Explanation of this
+// The event engine only skips the ticks where nothing happens, so it must give the same results as the tick engine,
+// which visits every tick. Every layout runs under both engines with the same seed, once with a light load and once
+// well above what the server handles (so the queues fill up and requests are dropped).
+
+const NR_PACKETS: usize = 500;
+
+fn config(layout: usize, rate: usize, engine: &'static str) -> SimConfig {
+    TestConfig::new(layout).rate(rate).engine(engine).nr_packets(NR_PACKETS).extra(r#"{"seed": 11}"#).build()
+}
+
+#[test]
+fn event_and_tick_engines_give_the_same_records() {
+    for layout in 1..=4 {
+        for rate in [300000, 5000000] {
+            let event: SimulationResults = common::run(0, &config(layout, rate, "event"));
+            let tick: SimulationResults = common::run(0, &config(layout, rate, "tick"));
+            assert_eq!(event.finished.len() + event.dropped.len(), NR_PACKETS, "layout {}, {} pps", layout, rate);
+            assert_eq!(event.dropped.is_empty(), rate < 1000000, "layout {}, {} pps: only the high load drops requests", layout, rate);
+            assert_eq!(event.finished, tick.finished, "layout {}, {} pps: the finished requests differ", layout, rate);
+            assert_eq!(event.dropped, tick.dropped, "layout {}, {} pps: the dropped requests differ", layout, rate);
+        }
+    }
+}
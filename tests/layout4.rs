@@ -0,0 +1,32 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+mod common;
+
+// Imports
+use sim::SimulationResults;
+
+use common::{
+    OVERLOAD,
+    TestConfig,
+};
+
+// Quick Explanation of this
+// In Layout 4, the requests that a network core finished wait in its ready queue for an idle application core of
+// its group. The queue is drained every round, so a request is never stranded there once the arrivals stop, even
+// when several application cores become idle in the same round.
+
+const NR_PACKETS: usize = 2000;
+
+#[test]
+fn no_request_is_stranded_in_the_ready_queue() {
+    for nr_network_cores in [1, 2, 4] {
+        let extra: String = format!(r#"{{"layout4": {{"nr_network_cores": {}, "nr_application_cores": {}}}}}"#, nr_network_cores, 8 - nr_network_cores);
+        let config = TestConfig::new(4).nr_packets(NR_PACKETS).extra(OVERLOAD).extra(&extra).build();
+        let results: SimulationResults = common::run(0, &config);
+        assert!(!results.dropped.is_empty(), "{} network cores: the server should be overloaded", nr_network_cores);
+        assert_eq!(
+            results.finished.len() + results.dropped.len(), NR_PACKETS,
+            "{} network cores: {} requests finished and {} were dropped", nr_network_cores, results.finished.len(), results.dropped.len(),
+        );
+    }
+}
@@ -0,0 +1,46 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+mod common;
+
+// Imports
+use sim::{
+    SimConfig,
+    SimulationResults,
+    pool,
+};
+
+use common::TestConfig;
+
+// Quick Explanation of this
+// The runs are independent (each one derives its own random streams from the seed and its run id), and the pool
+// returns the results by job index, so they must not depend on the number of threads. The jobs mix layouts and
+// run ids, so they take different times and finish out of order with several threads.
+
+fn config(layout: usize) -> SimConfig {
+    TestConfig::new(layout).nr_packets(200 * layout).extra(r#"{"seed": 3}"#).build()
+}
+
+fn run_all(configs: &[SimConfig], nr_threads: usize) -> Vec<SimulationResults> {
+    let nr_runs: usize = 3;
+    pool::run_jobs(configs.len() * nr_runs, nr_threads, |i| {
+        let config: &SimConfig = &configs[i / nr_runs];
+        common::run(i % nr_runs, config)
+    })
+}
+
+#[test]
+fn results_do_not_depend_on_the_number_of_threads() {
+    let configs: Vec<SimConfig> = (1..=4).map(config).collect();
+    let sequential: Vec<SimulationResults> = run_all(&configs, 1);
+    let parallel: Vec<SimulationResults> = run_all(&configs, 4);
+
+    assert_eq!(sequential.len(), parallel.len());
+    for (i, (a, b)) in sequential.iter().zip(parallel.iter()).enumerate() {
+        assert_eq!((a.layout_id, a.run_id), (b.layout_id, b.run_id), "job {}: results out of order", i);
+        assert_eq!(a.finished, b.finished, "job {}: the finished requests differ", i);
+        assert_eq!(a.dropped, b.dropped, "job {}: the dropped requests differ", i);
+        assert_eq!((a.t_start, a.t_end), (b.t_start, b.t_end), "job {}: the simulated time differs", i);
+    }
+    // The runs of a point draw different requests
+    assert_ne!(sequential[0].finished, sequential[1].finished);
+}
@@ -0,0 +1,92 @@
+// Created by Fabricio Carvalho (fabricio.carvalho@ufmt.br)
+
+mod common;
+
+// Imports
+use sim::{
+    ConfigError,
+    SimConfig,
+    SimulationResults,
+};
+
+use common::TestConfig;
+
+// Quick Explanation of this
+// Work stealing only happens when requests wait behind a busy core while a peer has nothing to run. Each layout gets
+// such an imbalance here: a single flow, so RSS sends every request to the same worker (Layout 2), or to the
+// application cores of the same network core (Layout 4), or a dispatcher that hands the requests to busy cores
+// (Layout 3). The layouts where stealing could never happen reject it.
+
+const NR_PACKETS: usize = 1000;
+
+fn config(layout: usize, extras: &[&str]) -> Result<SimConfig, ConfigError> {
+    let mut config: TestConfig = TestConfig::new(layout).rate(500000).queue_size(64).nr_packets(NR_PACKETS).extra(r#"{
+        "audit": true, "network_stack": {"mean1": 300}, "application": {"mean1": 5000}, "packets": {"nr_flows": 1}
+    }"#);
+    for extra in extras {
+        config = config.extra(extra);
+    }
+    config.try_build()
+}
+
+fn run(layout: usize, extras: &[&str]) -> SimulationResults {
+    common::run(0, &config(layout, extras).expect("valid configuration"))
+}
+
+fn nr_steals(results: &SimulationResults) -> usize {
+    results.core_stats.iter().map(|stats| stats.steals).sum()
+}
+
+fn mean_latency(results: &SimulationResults) -> f64 {
+    results.finished.iter().map(|record| (record.t_departure - record.t_arrival) as f64).sum::<f64>() / results.finished.len() as f64
+}
+
+#[test]
+fn idle_cores_steal_from_the_loaded_ones() {
+    let layouts: [(usize, &str); 3] = [
+        (2, "{}"),
+        (3, r#"{"dispatcher": {"policy": "round_robin"}}"#),
+        (4, "{}"),
+    ];
+    for (layout, extra) in layouts {
+        let without: SimulationResults = run(layout, &[extra]);
+        assert_eq!(nr_steals(&without), 0, "layout {}: a request was stolen without stealing", layout);
+
+        let free: SimulationResults = run(layout, &[extra, r#"{"stealing": {"policy": "longest", "cost": 0}}"#]);
+        let costly: SimulationResults = run(layout, &[extra, r#"{"stealing": {"policy": "longest", "cost": 20000}}"#]);
+        for results in [&free, &costly] {
+            assert!(nr_steals(results) > 0, "layout {}: no request was stolen", layout);
+            assert_eq!(results.finished.len() + results.dropped.len(), NR_PACKETS);
+        }
+
+        // Stealing for free shortens the queues, while each steal of 20 us costs more than it saves
+        assert!(mean_latency(&free) < mean_latency(&without), "layout {}: stealing for free did not help", layout);
+        assert!(mean_latency(&costly) > mean_latency(&free), "layout {}: the cost of the steals was not charged", layout);
+    }
+}
+
+#[test]
+fn stealing_needs_requests_waiting_behind_busy_cores() {
+    let stealing: &str = r#"{"stealing": {}}"#;
+    let rejected: [(usize, &str); 3] = [
+        (1, "{}"),
+        (3, "{}"),
+        (3, r#"{"discipline": {"policy": "srpt", "quantum": 1000}}"#),
+    ];
+    for (layout, extra) in rejected {
+        match config(layout, &[extra, stealing]) {
+            Err(ConfigError::Invalid { path, .. }) => assert_eq!(path, "stealing", "layout {}", layout),
+            Err(e) => panic!("layout {}: unexpected error {}", layout, e),
+            Ok(_) => panic!("layout {}: stealing should be rejected", layout),
+        }
+    }
+
+    let accepted: [(usize, &str); 3] = [
+        (2, "{}"),
+        (3, r#"{"dispatcher": {"policy": "jsq"}}"#),
+        (4, "{}"),
+    ];
+    for (layout, extra) in accepted {
+        config(layout, &[extra, stealing]).unwrap_or_else(|e| panic!("layout {}: {}", layout, e));
+    }
+}